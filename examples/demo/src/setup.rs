@@ -6,14 +6,15 @@ use bevy::{
     transform::TransformBundle,
     utils::default,
 };
-use bevy_openxr::xr_input::interactions::{Touched, XRInteractable, XRInteractableState};
+use bevy_openxr::xr_input::{
+    grabbable::{XrGrabMovement, XrGrabbable},
+    interactions::XrInteractableBundle,
+};
 use bevy_rapier3d::{
     prelude::{Collider, RigidBody},
     render::ColliderDebugColor,
 };
 
-use crate::Grabbable;
-
 /// set up a simple 3D scene
 pub fn setup_scene(
     mut commands: Commands,
@@ -47,10 +48,11 @@ pub fn setup_scene(
         RigidBody::Dynamic,
         Collider::cuboid(0.05, 0.05, 0.05),
         ColliderDebugColor(Color::hsl(220.0, 1.0, 0.3)),
-        XRInteractable,
-        XRInteractableState::default(),
-        Grabbable,
-        Touched(false),
+        XrInteractableBundle::default(),
+        XrGrabbable {
+            movement: XrGrabMovement::Kinematic,
+            ..default()
+        },
     ));
 
     // light
@@ -4,20 +4,22 @@ use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 
 use bevy::prelude::*;
 use bevy::transform::components::Transform;
-use bevy_oxr::input::XrInput;
-use bevy_oxr::resources::{XrFrameState, XrInstance, XrSession};
 
+use bevy_oxr::xr_input::gestures::{HandGestureChanged, HandGesturePlugin};
 use bevy_oxr::xr_input::hand::{OpenXrHandInput, HandInputDebugRenderer};
+use bevy_oxr::xr_input::grabbable::XrGrabbable;
 use bevy_oxr::xr_input::interactions::{
-    draw_interaction_gizmos, draw_socket_gizmos, interactions, socket_interactions,
-    update_interactable_states, InteractionEvent, Touched, XRDirectInteractor, XRInteractable,
-    XRInteractableState, XRInteractorState, XRRayInteractor, XRSocketInteractor,
+    XrDirectInteractorBundle, XrInteractableBundle, XrInteractableShape, XrInteractionPlugin,
+    XrRayInteractorBundle, XrSocketInteractorBundle,
 };
-use bevy_oxr::xr_input::oculus_touch::OculusController;
-use bevy_oxr::xr_input::prototype_locomotion::{proto_locomotion, PrototypeLocomotionConfig};
+use bevy_oxr::xr_input::interactor_input::XrInteractorInput;
+use bevy_oxr::xr_input::locomotion::XrLocomotionPlugin;
+use bevy_oxr::xr_input::ray_visuals::{XrRayVisual, XrRayVisualPlugin};
+use bevy_oxr::xr_input::teleport::{TeleportArea, XrTeleportPlugin};
 use bevy_oxr::xr_input::trackers::{
-    AimPose, OpenXRController, OpenXRLeftController, OpenXRRightController, OpenXRTracker,
+    OpenXRController, OpenXRLeftController, OpenXRRightController, OpenXRTracker,
 };
+use bevy_oxr::xr_input::ui_panel::{XrUiPanel, XrUiPanelPlugin, XrUiPointer};
 use bevy_oxr::xr_input::Hand;
 use bevy_oxr::DefaultXrPlugins;
 
@@ -31,25 +33,18 @@ fn main() {
         .add_plugins(LogDiagnosticsPlugin::default())
         .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_systems(Startup, setup)
-        .add_systems(Update, proto_locomotion)
-        .insert_resource(PrototypeLocomotionConfig::default())
+        .add_plugins(XrLocomotionPlugin)
+        .add_plugins(XrTeleportPlugin)
         .add_systems(Startup, spawn_controllers_example)
         .add_plugins(OpenXrHandInput)
         .add_plugins(HandInputDebugRenderer)
-        .add_systems(
-            Update,
-            draw_interaction_gizmos.after(update_interactable_states),
-        )
-        .add_systems(Update, draw_socket_gizmos.after(update_interactable_states))
-        .add_systems(Update, interactions.before(update_interactable_states))
-        .add_systems(
-            Update,
-            socket_interactions.before(update_interactable_states),
-        )
-        .add_systems(Update, prototype_interaction_input)
-        .add_systems(Update, update_interactable_states)
-        .add_systems(Update, update_grabbables.after(update_interactable_states))
-        .add_event::<InteractionEvent>()
+        .add_plugins(HandGesturePlugin)
+        .add_systems(Update, log_hand_gestures)
+        .add_plugins(XrInteractionPlugin)
+        .add_plugins(XrRayVisualPlugin)
+        .add_plugins(XrUiPanelPlugin)
+        .add_systems(Startup, spawn_menu)
+        .add_systems(Update, menu_button_colors)
         .run();
 }
 
@@ -60,11 +55,15 @@ fn setup(
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     // plane
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(shape::Plane::from_size(5.0).into()),
-        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
-        ..default()
-    });
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(shape::Plane::from_size(5.0).into()),
+            material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+            ..default()
+        },
+        TeleportArea,
+        XrInteractableShape::MeshAabb,
+    ));
     // cube
     commands.spawn(PbrBundle {
         mesh: meshes.add(Mesh::from(shape::Cube { size: 0.1 })),
@@ -78,8 +77,7 @@ fn setup(
             transform: Transform::from_xyz(0.0, 0.5, 1.0),
             ..default()
         },
-        XRInteractorState::Selecting,
-        XRSocketInteractor,
+        XrSocketInteractorBundle::default(),
     ));
 
     // light
@@ -110,10 +108,8 @@ fn setup(
             transform: Transform::from_xyz(0.0, 1.0, 0.0),
             ..default()
         },
-        XRInteractable,
-        XRInteractableState::default(),
-        Grabbable,
-        Touched(false),
+        XrInteractableBundle::default(),
+        XrGrabbable::default(),
     ));
 }
 
@@ -124,9 +120,10 @@ fn spawn_controllers_example(mut commands: Commands) {
         OpenXRController,
         OpenXRTracker,
         SpatialBundle::default(),
-        XRRayInteractor,
-        AimPose(Transform::default()),
-        XRInteractorState::default(),
+        XrRayInteractorBundle::default(),
+        XrRayVisual::default(),
+        XrInteractorInput::new(Hand::Left),
+        XrUiPointer::default().with_scroll_hand(Hand::Left),
     ));
     //right hand
     commands.spawn((
@@ -134,89 +131,75 @@ fn spawn_controllers_example(mut commands: Commands) {
         OpenXRController,
         OpenXRTracker,
         SpatialBundle::default(),
-        XRDirectInteractor,
-        XRInteractorState::default(),
+        XrDirectInteractorBundle::default(),
+        XrInteractorInput::new(Hand::Right),
     ));
 }
 
-fn prototype_interaction_input(
-    oculus_controller: Res<OculusController>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
-    mut right_interactor_query: Query<
-        (&mut XRInteractorState),
-        (
-            With<XRDirectInteractor>,
-            With<OpenXRRightController>,
-            Without<OpenXRLeftController>,
-        ),
-    >,
-    mut left_interactor_query: Query<
-        (&mut XRInteractorState),
-        (
-            With<XRRayInteractor>,
-            With<OpenXRLeftController>,
-            Without<OpenXRRightController>,
-        ),
-    >,
+fn spawn_menu(mut commands: Commands) {
+    //the ui shows up on the panel
+    commands.spawn((
+        SpatialBundle {
+            transform: Transform::from_xyz(-1.0, 1.3, -1.0)
+                .with_rotation(Quat::from_rotation_y(0.5)),
+            ..default()
+        },
+        XrUiPanel { width: 0.8 },
+    ));
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            background_color: Color::rgba(0.1, 0.1, 0.1, 0.8).into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(ButtonBundle {
+                    style: Style {
+                        width: Val::Px(300.0),
+                        height: Val::Px(100.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Press me",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn menu_button_colors(
+    mut buttons: Query<(&Interaction, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
 ) {
-    //lock frame
-    let frame_state = *frame_state.lock().unwrap();
-    //get controller
-    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
-    //get controller triggers
-    let left_trigger = controller.trigger(Hand::Left);
-    let right_trigger = controller.trigger(Hand::Right);
-    //get the interactors and do state stuff
-    let mut left_state = left_interactor_query.single_mut();
-    if left_trigger > 0.8 {
-        *left_state = XRInteractorState::Selecting;
-    } else {
-        *left_state = XRInteractorState::Idle;
-    }
-    let mut right_state = right_interactor_query.single_mut();
-    if right_trigger > 0.8 {
-        *right_state = XRInteractorState::Selecting;
-    } else {
-        *right_state = XRInteractorState::Idle;
+    for (interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::Pressed => Color::GREEN.into(),
+            Interaction::Hovered => Color::YELLOW.into(),
+            Interaction::None => Color::WHITE.into(),
+        };
     }
 }
 
-#[derive(Component)]
-pub struct Grabbable;
-
-pub fn update_grabbables(
-    mut events: EventReader<InteractionEvent>,
-    mut grabbable_query: Query<(&mut Transform, With<Grabbable>, Without<XRDirectInteractor>)>,
-    interactor_query: Query<(&GlobalTransform, &XRInteractorState, Without<Grabbable>)>,
-) {
-    //so basically the idea is to try all the events?
-    for event in events.read() {
-        // info!("some event");
-        match grabbable_query.get_mut(event.interactable) {
-            Ok(mut grabbable_transform) => {
-                // info!("we got a grabbable");
-                //now we need the location of our interactor
-                match interactor_query.get(event.interactor) {
-                    Ok(interactor_transform) => {
-                        match interactor_transform.1 {
-                            XRInteractorState::Idle => (),
-                            XRInteractorState::Selecting => {
-                                // info!("its a direct interactor?");
-                                *grabbable_transform.0 = interactor_transform.0.compute_transform();
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        // info!("not a direct interactor")
-                    }
-                }
-            }
-            Err(_) => {
-                // info!("not a grabbable?")
-            }
+fn log_hand_gestures(mut gesture_events: EventReader<HandGestureChanged>) {
+    for event in gesture_events.read() {
+        match event.active {
+            true => info!("{:?} hand started {:?}", event.hand, event.gesture),
+            false => info!("{:?} hand stopped {:?}", event.hand, event.gesture),
         }
     }
 }
@@ -0,0 +1,118 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::resource_macros::*;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use openxr as xr;
+
+/// An error in the OpenXR frame loop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrError {
+    /// a call into the runtime returned an error
+    Runtime {
+        /// the OpenXR function that failed, e.g. `xrWaitFrame`
+        call: &'static str,
+        result: xr::sys::Result,
+    },
+    /// the session is about to be lost, it has to be recreated to continue
+    SessionLossPending,
+    /// the instance is about to be lost, the runtime has to be restarted to continue
+    InstanceLossPending,
+}
+
+impl XrError {
+    pub fn runtime(call: &'static str, result: xr::sys::Result) -> Self {
+        Self::Runtime { call, result }
+    }
+
+    /// the result the runtime returned, if this came from a failed call
+    pub fn result(&self) -> Option<xr::sys::Result> {
+        match self {
+            XrError::Runtime { result, .. } => Some(*result),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for XrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XrError::Runtime { call, result } => write!(f, "{} failed: {}", call, result),
+            XrError::SessionLossPending => write!(f, "the XR session is being lost"),
+            XrError::InstanceLossPending => write!(f, "the XR instance is being lost"),
+        }
+    }
+}
+
+impl std::error::Error for XrError {}
+
+/// Sent for every error in the frame loop, the frame it happened in is skipped.
+///
+/// Whether the app keeps running afterwards is decided by the [`XrErrorPolicy`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct XrErrorEvent(pub XrError);
+
+/// Decides which [`XrError`]s shut the app down
+#[derive(Resource, Clone, Debug)]
+pub struct XrErrorPolicy {
+    /// runtime results that are fatal no matter which call returned them
+    pub fatal_results: Vec<xr::sys::Result>,
+    /// exit when the runtime announces that the session or instance will be lost
+    pub exit_on_loss_pending: bool,
+}
+
+impl Default for XrErrorPolicy {
+    fn default() -> Self {
+        Self {
+            fatal_results: vec![
+                xr::sys::Result::ERROR_INSTANCE_LOST,
+                xr::sys::Result::ERROR_SESSION_LOST,
+                xr::sys::Result::ERROR_RUNTIME_FAILURE,
+                xr::sys::Result::ERROR_OUT_OF_MEMORY,
+            ],
+            exit_on_loss_pending: true,
+        }
+    }
+}
+
+impl XrErrorPolicy {
+    pub fn is_fatal(&self, error: &XrError) -> bool {
+        match error {
+            XrError::Runtime { result, .. } => self.fatal_results.contains(result),
+            XrError::SessionLossPending | XrError::InstanceLossPending => self.exit_on_loss_pending,
+        }
+    }
+}
+
+// the render world can't send events to the main world, so its errors are queued here
+// and sent as events at the start of the next main world frame
+xr_arc_resource_wrapper!(XrRenderErrors, Mutex<Vec<XrError>>);
+
+impl XrRenderErrors {
+    pub fn push(&self, error: XrError) {
+        self.lock().unwrap().push(error);
+    }
+}
+
+pub fn forward_render_errors(
+    render_errors: Res<XrRenderErrors>,
+    mut error_events: EventWriter<XrErrorEvent>,
+) {
+    error_events.send_batch(render_errors.lock().unwrap().drain(..).map(XrErrorEvent));
+}
+
+pub fn handle_xr_errors(
+    mut error_events: EventReader<XrErrorEvent>,
+    policy: Res<XrErrorPolicy>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for XrErrorEvent(error) in error_events.read() {
+        if policy.is_fatal(error) {
+            error!("fatal XR error: {}", error);
+            app_exit.send(AppExit);
+        } else {
+            warn!("XR error: {}", error);
+        }
+    }
+}
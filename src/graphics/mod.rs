@@ -4,7 +4,7 @@ use bevy::render::renderer::{RenderAdapter, RenderAdapterInfo, RenderDevice, Ren
 use bevy::window::RawHandleWrapper;
 use wgpu::Instance;
 
-use crate::input::XrInput;
+use crate::input::{XrInput, XrReferenceSpacePreference};
 use crate::resources::{
     XrEnvironmentBlendMode, XrFormat, XrFrameState, XrFrameWaiter, XrInstance, XrResolution,
     XrSession, XrSessionRunning, XrSwapchain, XrViews,
@@ -14,6 +14,7 @@ use openxr as xr;
 
 pub fn initialize_xr_graphics(
     window: Option<RawHandleWrapper>,
+    reference_space: &XrReferenceSpacePreference,
 ) -> anyhow::Result<(
     RenderDevice,
     RenderQueue,
@@ -32,7 +33,7 @@ pub fn initialize_xr_graphics(
     XrViews,
     XrFrameState,
 )> {
-    vulkan::initialize_xr_graphics(window)
+    vulkan::initialize_xr_graphics(window, reference_space)
 }
 
 pub fn xr_entry() -> xr::Entry {
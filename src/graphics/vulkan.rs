@@ -11,7 +11,7 @@ use bevy::window::RawHandleWrapper;
 use openxr as xr;
 use wgpu::Instance;
 
-use crate::input::XrInput;
+use crate::input::{XrInput, XrReferenceSpacePreference};
 use crate::resources::{
     Swapchain, SwapchainInner, XrEnvironmentBlendMode, XrFormat, XrFrameState, XrFrameWaiter,
     XrInstance, XrResolution, XrSession, XrSessionRunning, XrSwapchain, XrViews,
@@ -20,6 +20,7 @@ use crate::VIEW_TYPE;
 
 pub fn initialize_xr_graphics(
     window: Option<RawHandleWrapper>,
+    reference_space: &XrReferenceSpacePreference,
 ) -> anyhow::Result<(
     RenderDevice,
     RenderQueue,
@@ -57,8 +58,21 @@ pub fn initialize_xr_graphics(
         enabled_extensions.khr_android_create_instance = true;
     }
     enabled_extensions.ext_hand_tracking = available_extensions.ext_hand_tracking;
-    // enabled_extensions.ext_hand_joints_motion_range = available_extensions.ext_hand_joints_motion_range;
-    
+    enabled_extensions.ext_hand_joints_motion_range =
+        available_extensions.ext_hand_joints_motion_range;
+    enabled_extensions.fb_hand_tracking_mesh = available_extensions.fb_hand_tracking_mesh;
+    enabled_extensions.fb_hand_tracking_aim = available_extensions.fb_hand_tracking_aim;
+    enabled_extensions.msft_unbounded_reference_space =
+        available_extensions.msft_unbounded_reference_space;
+    if available_extensions
+        .other
+        .iter()
+        .any(|ext| ext == "XR_EXT_local_floor")
+    {
+        enabled_extensions
+            .other
+            .push("XR_EXT_local_floor".to_string());
+    }
 
     let available_layers = xr_entry.enumerate_layers()?;
     info!("available xr layers: {:#?}", available_layers);
@@ -398,13 +412,13 @@ pub fn initialize_xr_graphics(
             image_index: Mutex::new(0),
         })
         .into(),
-        XrInput::new(xr_instance, session.into_any_graphics())?,
-        Mutex::default().into(),
-        Mutex::new(xr::FrameState {
+        XrInput::new(xr_instance, session.into_any_graphics(), &reference_space.0)?,
+        Vec::new().into(),
+        xr::FrameState {
             predicted_display_time: xr::Time::from_nanos(1),
             predicted_display_period: xr::Duration::from_nanos(1),
             should_render: true,
-        })
+        }
         .into(),
     ))
 }
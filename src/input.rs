@@ -3,18 +3,105 @@ use std::sync::Arc;
 use bevy::prelude::*;
 use openxr as xr;
 
+use crate::xr_input::{QuatConv, Vec3Conv};
+
+/// raw value of `XR_REFERENCE_SPACE_TYPE_LOCAL_FLOOR_EXT`, the bindings don't have it yet
+const LOCAL_FLOOR_EXT: i32 = 1000426000;
+
+/// The reference spaces the tracking origin can be built from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XrReferenceSpaceType {
+    /// Seated origin at the head position when the app started, not floor aligned
+    Local,
+    /// Room scale origin at the center of the play area on the floor
+    Stage,
+    /// Like `Local` but on the floor, needs `XR_EXT_local_floor`
+    LocalFloor,
+    /// World scale space for walking outside of a play area, needs `XR_MSFT_unbounded_reference_space`
+    Unbounded,
+}
+
+impl XrReferenceSpaceType {
+    pub fn to_xr(self) -> xr::ReferenceSpaceType {
+        match self {
+            XrReferenceSpaceType::Local => xr::ReferenceSpaceType::LOCAL,
+            XrReferenceSpaceType::Stage => xr::ReferenceSpaceType::STAGE,
+            XrReferenceSpaceType::LocalFloor => xr::ReferenceSpaceType::from_raw(LOCAL_FLOOR_EXT),
+            XrReferenceSpaceType::Unbounded => xr::ReferenceSpaceType::UNBOUNDED_MSFT,
+        }
+    }
+
+    pub fn from_xr(ty: xr::ReferenceSpaceType) -> Option<Self> {
+        [
+            XrReferenceSpaceType::Local,
+            XrReferenceSpaceType::Stage,
+            XrReferenceSpaceType::LocalFloor,
+            XrReferenceSpaceType::Unbounded,
+        ]
+        .into_iter()
+        .find(|space| space.to_xr() == ty)
+    }
+
+    /// whether the origin of this space lies on the floor
+    pub fn is_floor_level(self) -> bool {
+        !matches!(self, XrReferenceSpaceType::Local)
+    }
+}
+
+/// Reference space types to try in order, the first one supported by the runtime is used.
+/// `Local` is always supported so it is used if nothing else in the list is.
+#[derive(Clone, Debug, Resource)]
+pub struct XrReferenceSpacePreference(pub Vec<XrReferenceSpaceType>);
+
+impl Default for XrReferenceSpacePreference {
+    fn default() -> Self {
+        Self(vec![
+            XrReferenceSpaceType::Stage,
+            XrReferenceSpaceType::LocalFloor,
+            XrReferenceSpaceType::Local,
+        ])
+    }
+}
+
+/// Send this to recenter the tracking space on the current head position and yaw
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct XrRecenter;
+
+/// Sent when the runtime announces a change of a reference space origin,
+/// for example because the user recentered from the system menu
+#[derive(Event, Clone, Copy, Debug)]
+pub struct XrReferenceSpaceChanged {
+    pub space_type: Option<XrReferenceSpaceType>,
+    pub change_time: xr::Time,
+    /// the new origin expressed in the old space, if the runtime knows it
+    pub pose_in_previous_space: Option<Transform>,
+}
+
+/// Sent when the runtime changed the interaction profile bound to any top level user path,
+/// e.g. when controllers are put down or picked up
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct XrInteractionProfileChanged;
+
 #[derive(Clone, Resource)]
 pub struct XrInput {
     //pub action_set: xr::ActionSet,
     //pub hand_pose: xr::Action<xr::Posef>,
     //pub right_space: Arc<xr::Space>,
     //pub left_space: Arc<xr::Space>,
+    /// the tracking space everything is located in, not necessarily of type `STAGE`
     pub stage: Arc<xr::Space>,
     pub head: Arc<xr::Space>,
+    pub reference_space_type: XrReferenceSpaceType,
+    /// offset of `stage` from the origin of the underlying reference space, set by recentering
+    pub recenter_pose: xr::Posef,
 }
 
 impl XrInput {
-    pub fn new(_instance: xr::Instance, session: xr::Session<xr::AnyGraphics>) -> xr::Result<Self> {
+    pub fn new(
+        _instance: xr::Instance,
+        session: xr::Session<xr::AnyGraphics>,
+        preference: &[XrReferenceSpaceType],
+    ) -> xr::Result<Self> {
         // let action_set = instance.create_action_set("input", "input pose information", 0)?;
         // let left_hand_subaction_path = instance.string_to_path("/user/hand/left").unwrap();
         // let right_hand_subaction_path = instance.string_to_path("/user/hand/right").unwrap();
@@ -49,11 +136,17 @@ impl XrInput {
         //     left_hand_subaction_path,
         //     xr::Posef::IDENTITY,
         // )?;
+        let available = session.enumerate_reference_spaces()?;
+        let reference_space_type = preference
+            .iter()
+            .copied()
+            .find(|ty| available.contains(&ty.to_xr()))
+            .unwrap_or(XrReferenceSpaceType::Local);
+        info!("using {:?} reference space", reference_space_type);
         let stage =
-            session.create_reference_space(xr::ReferenceSpaceType::STAGE, xr::Posef::IDENTITY)?;
-        let head = session
-            .create_reference_space(xr::ReferenceSpaceType::VIEW, xr::Posef::IDENTITY)
-            .unwrap();
+            session.create_reference_space(reference_space_type.to_xr(), xr::Posef::IDENTITY)?;
+        let head =
+            session.create_reference_space(xr::ReferenceSpaceType::VIEW, xr::Posef::IDENTITY)?;
         //session.attach_action_sets(&[&action_set])?;
         //session.attach_action_sets(&[])?;
         Ok(Self {
@@ -63,6 +156,73 @@ impl XrInput {
             // left_space: Arc::new(left_space),
             stage: Arc::new(stage),
             head: Arc::new(head),
+            reference_space_type,
+            recenter_pose: xr::Posef::IDENTITY,
+        })
+    }
+
+    /// Creates a copy of this input whose tracking space is moved under the head at `time`,
+    /// keeping only the yaw of the head so the floor stays level.
+    pub fn recentered(
+        &self,
+        session: &xr::Session<xr::AnyGraphics>,
+        time: xr::Time,
+    ) -> xr::Result<Self> {
+        let location = self.head.locate(&self.stage, time)?;
+        if !location.location_flags.contains(
+            xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID,
+        ) {
+            warn!("head pose is not valid, not recentering");
+            return Ok(self.clone());
+        }
+        let mut translation = location.pose.position.to_vec3();
+        if self.reference_space_type.is_floor_level() {
+            translation.y = 0.0;
+        }
+        let forward = location.pose.orientation.to_quat().mul_vec3(Vec3::NEG_Z);
+        let yaw = Quat::from_rotation_y(f32::atan2(-forward.x, -forward.z));
+        let head = Transform::from_translation(translation).with_rotation(yaw);
+        let offset = pose_to_transform(self.recenter_pose).mul_transform(head);
+        self.with_recenter_pose(session, transform_to_pose(offset))
+    }
+
+    /// Creates a copy of this input with its tracking space offset by `recenter_pose`
+    pub fn with_recenter_pose(
+        &self,
+        session: &xr::Session<xr::AnyGraphics>,
+        recenter_pose: xr::Posef,
+    ) -> xr::Result<Self> {
+        let stage =
+            session.create_reference_space(self.reference_space_type.to_xr(), recenter_pose)?;
+        Ok(Self {
+            stage: Arc::new(stage),
+            recenter_pose,
+            ..self.clone()
         })
     }
 }
+
+pub fn pose_to_transform(pose: xr::Posef) -> Transform {
+    Transform::from_translation(pose.position.to_vec3()).with_rotation(pose.orientation.to_quat())
+}
+
+pub fn transform_to_pose(transform: Transform) -> xr::Posef {
+    let Transform {
+        translation,
+        rotation,
+        ..
+    } = transform;
+    xr::Posef {
+        orientation: xr::Quaternionf {
+            x: rotation.x,
+            y: rotation.y,
+            z: rotation.z,
+            w: rotation.w,
+        },
+        position: xr::Vector3f {
+            x: translation.x,
+            y: translation.y,
+            z: translation.z,
+        },
+    }
+}
@@ -1,3 +1,30 @@
+//! # Frame timing
+//!
+//! An XR frame goes through these steps, the main world ones are exposed as [`XrFrameSet`]:
+//!
+//! 1. `XrFrameSet::BeginFrame` (`PreUpdate`): events are polled, the frame timing is taken from
+//!    the [`XrFrameWaitThread`] which ran `xrWaitFrame` in the background and waiting for the
+//!    next frame starts right away.
+//! 2. `XrFrameSet::LocatePoses` (`PreUpdate`): actions are synced, the views, cameras and
+//!    controllers are located at the predicted display time for game logic in `Update`.
+//! 3. `XrFrameSet::LateLatch` (`PostUpdate`, before transform propagation): the views, cameras
+//!    and trackers are located again at the same predicted time, which gives the runtime a
+//!    fresher prediction right before the world is extracted for rendering.
+//! 4. Render world: [`XrFrameState`], [`XrViews`] and [`XrFramePending`] are extracted with the
+//!    rest of the world, the frame is begun and the swapchain image acquired before rendering,
+//!    then the image is released and the frame ended with exactly the extracted views.
+//!
+//! All per-frame state reaches the render world through extraction, so this works with
+//! bevy's `PipelinedRenderingPlugin` where rendering a frame overlaps simulating the next one.
+//!
+//! # Errors
+//!
+//! Runtime errors in the frame loop skip the frame and are sent as [`error::XrErrorEvent`]s,
+//! including the ones from the render world. The [`error::XrErrorPolicy`] resource decides which
+//! of them exit the app. Frames the runtime doesn't want rendered (`should_render` is false) are
+//! begun and ended without layers and the XR cameras are deactivated for them.
+
+pub mod error;
 mod graphics;
 pub mod input;
 pub mod resource_macros;
@@ -12,16 +39,22 @@ use bevy::app::PluginGroupBuilder;
 use bevy::ecs::system::SystemState;
 use bevy::prelude::*;
 use bevy::render::camera::{ManualTextureView, ManualTextureViewHandle, ManualTextureViews};
-use bevy::render::pipelined_rendering::PipelinedRenderingPlugin;
 use bevy::render::renderer::{render_system, RenderInstance};
 use bevy::render::settings::RenderCreation;
-use bevy::render::{Render, RenderApp, RenderPlugin, RenderSet};
+use bevy::render::{Extract, ExtractSchedule, Render, RenderApp, RenderPlugin, RenderSet};
+use bevy::transform::TransformSystem;
 use bevy::window::{PresentMode, PrimaryWindow, RawHandleWrapper};
-use input::XrInput;
+use error::{
+    forward_render_errors, handle_xr_errors, XrError, XrErrorEvent, XrErrorPolicy, XrRenderErrors,
+};
+use input::{
+    pose_to_transform, XrInput, XrInteractionProfileChanged, XrRecenter, XrReferenceSpaceChanged,
+    XrReferenceSpacePreference, XrReferenceSpaceType,
+};
 use openxr as xr;
 use resources::*;
 use xr_input::controllers::XrControllerType;
-use xr_input::handtracking::HandTrackingTracker;
+use xr_input::handtracking::HandTrackingPlugin;
 use xr_input::OpenXrInput;
 
 const VIEW_TYPE: xr::ViewConfigurationType = xr::ViewConfigurationType::PRIMARY_STEREO;
@@ -29,9 +62,23 @@ const VIEW_TYPE: xr::ViewConfigurationType = xr::ViewConfigurationType::PRIMARY_
 pub const LEFT_XR_TEXTURE_HANDLE: ManualTextureViewHandle = ManualTextureViewHandle(1208214591);
 pub const RIGHT_XR_TEXTURE_HANDLE: ManualTextureViewHandle = ManualTextureViewHandle(3383858418);
 
+/// The main world parts of an XR frame, in the order they run
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XrFrameSet {
+    /// poll events and get the frame timing, in `PreUpdate`
+    BeginFrame,
+    /// locate everything for this frame, in `PreUpdate`
+    LocatePoses,
+    /// locate everything again right before extraction, in `PostUpdate`
+    LateLatch,
+}
+
 /// Adds OpenXR support to an App
 #[derive(Default)]
-pub struct OpenXrPlugin;
+pub struct OpenXrPlugin {
+    /// which reference space the tracking origin is created from
+    pub reference_space: XrReferenceSpacePreference,
+}
 
 #[derive(Resource)]
 pub struct FutureXrResources(
@@ -80,7 +127,7 @@ impl Plugin for OpenXrPlugin {
             input,
             views,
             frame_state,
-        ) = graphics::initialize_xr_graphics(primary_window).unwrap();
+        ) = graphics::initialize_xr_graphics(primary_window, &self.reference_space).unwrap();
         // std::thread::sleep(Duration::from_secs(5));
         debug!("Configured wgpu adapter Limits: {:#?}", device.limits());
         debug!("Configured wgpu adapter Features: {:#?}", device.features());
@@ -99,6 +146,20 @@ impl Plugin for OpenXrPlugin {
             frame_state,
         ));
         app.insert_resource(ActionSets(vec![]));
+        app.add_event::<XrRecenter>();
+        app.add_event::<XrReferenceSpaceChanged>();
+        app.add_event::<XrInteractionProfileChanged>();
+        app.add_event::<XrErrorEvent>();
+        app.init_resource::<XrErrorPolicy>();
+        app.add_systems(Last, handle_xr_errors);
+        app.configure_sets(
+            PreUpdate,
+            (XrFrameSet::BeginFrame, XrFrameSet::LocatePoses).chain(),
+        );
+        app.configure_sets(
+            PostUpdate,
+            XrFrameSet::LateLatch.before(TransformSystem::TransformPropagate),
+        );
         app.add_plugins(RenderPlugin {
             render_creation: RenderCreation::Manual(
                 device,
@@ -146,8 +207,7 @@ impl Plugin for OpenXrPlugin {
                 .insert_resource(input.clone())
                 .insert_resource(views.clone())
                 .insert_resource(frame_state.clone())
-                .insert_resource(action_sets.clone())
-                .insert_resource(HandTrackingTracker::new(&session).unwrap());
+                .insert_resource(action_sets.clone());
 
             let (left, right) = swapchain.get_render_views();
             let left = ManualTextureView {
@@ -160,7 +220,22 @@ impl Plugin for OpenXrPlugin {
                 size: *resolution,
                 format: *format,
             };
-            app.add_systems(PreUpdate, xr_begin_frame);
+            let render_errors = XrRenderErrors::new(Mutex::default());
+            app.insert_resource(XrFrameWaitThread::spawn(frame_waiter.clone()))
+                .insert_resource(XrFramePending::default())
+                .insert_resource(render_errors.clone());
+            app.add_systems(
+                PreUpdate,
+                (
+                    forward_render_errors,
+                    update_reference_space,
+                    xr_begin_frame,
+                )
+                    .chain()
+                    .in_set(XrFrameSet::BeginFrame),
+            );
+            app.add_systems(PreUpdate, locate_views.in_set(XrFrameSet::LocatePoses));
+            app.add_systems(PostUpdate, locate_views.in_set(XrFrameSet::LateLatch));
             let mut manual_texture_views = app.world.resource_mut::<ManualTextureViews>();
             manual_texture_views.insert(LEFT_XR_TEXTURE_HANDLE, left);
             manual_texture_views.insert(RIGHT_XR_TEXTURE_HANDLE, right);
@@ -179,8 +254,11 @@ impl Plugin for OpenXrPlugin {
                 .insert_resource(input)
                 .insert_resource(views)
                 .insert_resource(frame_state)
+                .insert_resource(XrFramePending::default())
+                .insert_resource(render_errors)
                 .insert_resource(action_sets);
 
+            render_app.add_systems(ExtractSchedule, (extract_xr_input, extract_xr_frame));
             render_app.add_systems(
                 Render,
                 (
@@ -201,9 +279,9 @@ impl PluginGroup for DefaultXrPlugins {
         DefaultPlugins
             .build()
             .disable::<RenderPlugin>()
-            .disable::<PipelinedRenderingPlugin>()
-            .add_before::<RenderPlugin, _>(OpenXrPlugin)
+            .add_before::<RenderPlugin, _>(OpenXrPlugin::default())
             .add_after::<OpenXrPlugin, _>(OpenXrInput::new(XrControllerType::OculusTouch))
+            .add_after::<OpenXrInput, _>(HandTrackingPlugin)
             .set(WindowPlugin {
                 #[cfg(not(target_os = "android"))]
                 primary_window: Some(Window {
@@ -225,15 +303,26 @@ pub fn xr_begin_frame(
     instance: Res<XrInstance>,
     session: Res<XrSession>,
     session_running: Res<XrSessionRunning>,
-    frame_state: Res<XrFrameState>,
-    frame_waiter: Res<XrFrameWaiter>,
-    swapchain: Res<XrSwapchain>,
-    views: Res<XrViews>,
-    input: Res<XrInput>,
+    mut frame_state: ResMut<XrFrameState>,
+    mut frame_pending: ResMut<XrFramePending>,
+    frame_wait_thread: Res<XrFrameWaitThread>,
+    mut space_changed: EventWriter<XrReferenceSpaceChanged>,
+    mut interaction_profile_changed: EventWriter<XrInteractionProfileChanged>,
+    mut error_events: EventWriter<XrErrorEvent>,
 ) {
+    frame_pending.0 = false;
     {
         let _span = info_span!("xr_poll_events");
-        while let Some(event) = instance.poll_event(&mut Default::default()).unwrap() {
+        let mut buffer = xr::EventDataBuffer::new();
+        loop {
+            let event = match instance.poll_event(&mut buffer) {
+                Ok(Some(event)) => event,
+                Ok(None) => break,
+                Err(err) => {
+                    error_events.send(XrErrorEvent(XrError::runtime("xrPollEvent", err)));
+                    break;
+                }
+            };
             use xr::Event::*;
             match event {
                 SessionStateChanged(e) => {
@@ -241,19 +330,45 @@ pub fn xr_begin_frame(
                     // find quit messages!
                     info!("entered XR state {:?}", e.state());
                     match e.state() {
-                        xr::SessionState::READY => {
-                            session.begin(VIEW_TYPE).unwrap();
-                            session_running.store(true, std::sync::atomic::Ordering::Relaxed);
-                        }
+                        xr::SessionState::READY => match session.begin(VIEW_TYPE) {
+                            Ok(_) => {
+                                session_running.store(true, std::sync::atomic::Ordering::Relaxed)
+                            }
+                            Err(err) => error_events
+                                .send(XrErrorEvent(XrError::runtime("xrBeginSession", err))),
+                        },
                         xr::SessionState::STOPPING => {
-                            session.end().unwrap();
+                            if let Err(err) = session.end() {
+                                error_events
+                                    .send(XrErrorEvent(XrError::runtime("xrEndSession", err)));
+                            }
                             session_running.store(false, std::sync::atomic::Ordering::Relaxed);
                         }
-                        xr::SessionState::EXITING | xr::SessionState::LOSS_PENDING => return,
+                        xr::SessionState::EXITING => return,
+                        xr::SessionState::LOSS_PENDING => {
+                            error_events.send(XrErrorEvent(XrError::SessionLossPending));
+                            return;
+                        }
                         _ => {}
                     }
                 }
-                InstanceLossPending(_) => return,
+                InstanceLossPending(_) => {
+                    error_events.send(XrErrorEvent(XrError::InstanceLossPending));
+                    return;
+                }
+                ReferenceSpaceChangePending(e) => {
+                    info!("XR reference space {:?} changing", e.reference_space_type());
+                    space_changed.send(XrReferenceSpaceChanged {
+                        space_type: XrReferenceSpaceType::from_xr(e.reference_space_type()),
+                        change_time: e.change_time(),
+                        pose_in_previous_space: e
+                            .pose_valid()
+                            .then(|| pose_to_transform(e.pose_in_previous_space())),
+                    });
+                }
+                InteractionProfileChanged(_) => {
+                    interaction_profile_changed.send(XrInteractionProfileChanged);
+                }
                 EventsLost(e) => {
                     warn!("lost {} XR events", e.lost_event_count());
                 }
@@ -261,46 +376,62 @@ pub fn xr_begin_frame(
             }
         }
     }
+    // waiting for frames only works while the session runs, until then no frames are rendered
+    if !session_running.load(std::sync::atomic::Ordering::Relaxed) {
+        return;
+    }
     {
         let _span = info_span!("xr_wait_frame").entered();
-        *frame_state.lock().unwrap() = match frame_waiter.lock().unwrap().wait() {
-            Ok(a) => a,
-            Err(e) => {
-                warn!("error: {}", e);
+        *frame_state = match frame_wait_thread.receive() {
+            Ok(a) => a.into(),
+            Err(err) => {
+                error_events.send(XrErrorEvent(XrError::runtime("xrWaitFrame", err)));
                 return;
             }
         };
     }
-    {
-        let _span = info_span!("xr_begin_frame").entered();
-        swapchain.begin().unwrap()
-    }
-    {
-        let _span = info_span!("xr_locate_views").entered();
-        *views.lock().unwrap() = session
-            .locate_views(
-                VIEW_TYPE,
-                frame_state.lock().unwrap().predicted_display_time,
-                &input.stage,
-            )
-            .unwrap()
-            .1;
-    }
+    // the render world begins this frame after extraction, the runtime holds the next wait
+    // until then so it still overlaps with simulating and rendering this frame
+    frame_pending.0 = true;
+    frame_wait_thread.request();
 }
 
 pub fn post_frame(
     resolution: Res<XrResolution>,
     format: Res<XrFormat>,
     swapchain: Res<XrSwapchain>,
+    xr_frame_state: Res<XrFrameState>,
+    environment_blend_mode: Res<XrEnvironmentBlendMode>,
+    mut frame_pending: ResMut<XrFramePending>,
+    render_errors: Res<XrRenderErrors>,
     mut manual_texture_views: ResMut<ManualTextureViews>,
 ) {
-    {
-        let _span = info_span!("xr_acquire_image").entered();
-        swapchain.acquire_image().unwrap()
+    if !frame_pending.0 {
+        return;
     }
     {
-        let _span = info_span!("xr_wait_image").entered();
-        swapchain.wait_image().unwrap();
+        let _span = info_span!("xr_begin_frame").entered();
+        if let Err(err) = swapchain.begin() {
+            render_errors.push(XrError::runtime("xrBeginFrame", err));
+            frame_pending.0 = false;
+            return;
+        }
+    }
+    // the runtime doesn't show this frame, end_frame ends it without layers
+    if !xr_frame_state.should_render {
+        return;
+    }
+    if let Err(err) = acquire_image(&swapchain) {
+        render_errors.push(err);
+        // a begun frame always has to be ended
+        if let Err(err) = swapchain.end_without_layers(
+            xr_frame_state.predicted_display_time,
+            **environment_blend_mode,
+        ) {
+            render_errors.push(XrError::runtime("xrEndFrame", err));
+        }
+        frame_pending.0 = false;
+        return;
     }
     {
         let _span = info_span!("xr_update_manual_texture_views").entered();
@@ -320,6 +451,21 @@ pub fn post_frame(
     }
 }
 
+fn acquire_image(swapchain: &XrSwapchain) -> Result<(), XrError> {
+    {
+        let _span = info_span!("xr_acquire_image").entered();
+        swapchain
+            .acquire_image()
+            .map_err(|err| XrError::runtime("xrAcquireSwapchainImage", err))?;
+    }
+    {
+        let _span = info_span!("xr_wait_image").entered();
+        swapchain
+            .wait_image()
+            .map_err(|err| XrError::runtime("xrWaitSwapchainImage", err))
+    }
+}
+
 pub fn end_frame(
     xr_frame_state: Res<XrFrameState>,
     views: Res<XrViews>,
@@ -327,42 +473,103 @@ pub fn end_frame(
     swapchain: Res<XrSwapchain>,
     resolution: Res<XrResolution>,
     environment_blend_mode: Res<XrEnvironmentBlendMode>,
+    frame_pending: Res<XrFramePending>,
+    render_errors: Res<XrRenderErrors>,
 ) {
-    {
-        let _span = info_span!("xr_release_image").entered();
-        swapchain.release_image().unwrap();
+    if !frame_pending.0 {
+        return;
     }
-    {
-        let _span = info_span!("xr_end_frame").entered();
-        swapchain
-            .end(
-                xr_frame_state.lock().unwrap().predicted_display_time,
-                &*views.lock().unwrap(),
-                &input.stage,
-                **resolution,
-                **environment_blend_mode,
-            )
-            .unwrap();
+    let _span = info_span!("xr_end_frame").entered();
+    let result = if xr_frame_state.should_render {
+        {
+            let _span = info_span!("xr_release_image").entered();
+            if let Err(err) = swapchain.release_image() {
+                render_errors.push(XrError::runtime("xrReleaseSwapchainImage", err));
+            }
+        }
+        swapchain.end(
+            xr_frame_state.predicted_display_time,
+            &views,
+            &input.stage,
+            **resolution,
+            **environment_blend_mode,
+        )
+    } else {
+        swapchain.end_without_layers(
+            xr_frame_state.predicted_display_time,
+            **environment_blend_mode,
+        )
+    };
+    if let Err(err) = result {
+        render_errors.push(XrError::runtime("xrEndFrame", err));
     }
 }
 
 pub fn locate_views(
-    views: Res<XrViews>,
+    mut views: ResMut<XrViews>,
     input: Res<XrInput>,
     session: Res<XrSession>,
     xr_frame_state: Res<XrFrameState>,
+    mut error_events: EventWriter<XrErrorEvent>,
 ) {
     let _span = info_span!("xr_locate_views").entered();
-    *views.lock().unwrap() = match session.locate_views(
+    *views = match session.locate_views(
         VIEW_TYPE,
-        xr_frame_state.lock().unwrap().predicted_display_time,
+        xr_frame_state.predicted_display_time,
         &input.stage,
     ) {
-        Ok(this) => this,
+        Ok(this) => this.1.into(),
         Err(err) => {
-            warn!("error: {}", err);
+            error_events.send(XrErrorEvent(XrError::runtime("xrLocateViews", err)));
             return;
         }
+    };
+}
+
+/// Applies recenter requests and drops our own recentering offset when the runtime moves the
+/// origin itself, runs before `xr_begin_frame` so views of a frame are all in the same space.
+pub fn update_reference_space(
+    mut recenter_events: EventReader<XrRecenter>,
+    mut space_changed_events: EventReader<XrReferenceSpaceChanged>,
+    mut input: ResMut<XrInput>,
+    session: Res<XrSession>,
+    frame_state: Res<XrFrameState>,
+) {
+    let space_type = Some(input.reference_space_type);
+    if space_changed_events
+        .read()
+        .filter(|event| event.space_type == space_type)
+        .count()
+        > 0
+    {
+        match input.with_recenter_pose(&session, xr::Posef::IDENTITY) {
+            Ok(new_input) => *input = new_input,
+            Err(err) => warn!("error resetting reference space: {}", err),
+        }
     }
-    .1;
+    if recenter_events.read().count() > 0 {
+        let time = frame_state.predicted_display_time;
+        match input.recentered(&session, time) {
+            Ok(new_input) => *input = new_input,
+            Err(err) => warn!("error recentering reference space: {}", err),
+        }
+    }
+}
+
+pub fn extract_xr_input(mut commands: Commands, input: Extract<Res<XrInput>>) {
+    if input.is_changed() {
+        commands.insert_resource(XrInput::clone(&input));
+    }
+}
+
+/// Hands the state of the frame that was just simulated to the render world
+pub fn extract_xr_frame(
+    mut commands: Commands,
+    frame_state: Extract<Res<XrFrameState>>,
+    views: Extract<Res<XrViews>>,
+    frame_pending: Extract<Res<XrFramePending>>,
+) {
+    commands.insert_resource(XrFrameState::clone(&frame_state));
+    commands.insert_resource(XrViews::clone(&views));
+    commands.insert_resource(**frame_pending);
 }
@@ -1,5 +1,5 @@
-use std::sync::atomic::AtomicBool;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
 
 use crate::resource_macros::*;
 use bevy::prelude::*;
@@ -13,8 +13,69 @@ xr_resource_wrapper!(XrFormat, wgpu::TextureFormat);
 xr_arc_resource_wrapper!(XrSessionRunning, AtomicBool);
 xr_arc_resource_wrapper!(XrFrameWaiter, Mutex<xr::FrameWaiter>);
 xr_arc_resource_wrapper!(XrSwapchain, Swapchain);
-xr_arc_resource_wrapper!(XrFrameState, Mutex<xr::FrameState>);
-xr_arc_resource_wrapper!(XrViews, Mutex<Vec<xr::View>>);
+xr_resource_wrapper!(XrFrameState, xr::FrameState);
+xr_resource_wrapper!(XrViews, Vec<xr::View>);
+
+/// Whether the frame in [`XrFrameState`] still has to be begun and ended by the render world.
+///
+/// The main world sets this when it got a new frame from `xrWaitFrame`, the render world
+/// gets a copy during extraction so every waited frame is begun exactly once, even when
+/// rendering runs one frame behind the simulation.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct XrFramePending(pub bool);
+
+/// Calls `xrWaitFrame` on a separate thread so the main thread only blocks on it when
+/// the simulation of a frame finished before the runtime is ready for the next one.
+#[derive(Resource)]
+pub struct XrFrameWaitThread {
+    requests: Mutex<mpsc::Sender<()>>,
+    frame_states: Mutex<mpsc::Receiver<xr::Result<xr::FrameState>>>,
+    pending: AtomicBool,
+}
+
+impl XrFrameWaitThread {
+    pub fn spawn(frame_waiter: XrFrameWaiter) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<()>();
+        let (state_sender, state_receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("xr_wait_frame".into())
+            .spawn(move || {
+                // exits once the resource and with it the request sender is dropped
+                while request_receiver.recv().is_ok() {
+                    let frame_state = frame_waiter.lock().unwrap().wait();
+                    if state_sender.send(frame_state).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn the xr_wait_frame thread");
+        Self {
+            requests: Mutex::new(request_sender),
+            frame_states: Mutex::new(state_receiver),
+            pending: AtomicBool::new(false),
+        }
+    }
+
+    /// start waiting for the next frame in the background, does nothing if a wait is already running
+    pub fn request(&self) {
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            let _ = self.requests.lock().unwrap().send(());
+        }
+    }
+
+    /// blocks until the runtime is ready for the next frame and returns its timing
+    pub fn receive(&self) -> xr::Result<xr::FrameState> {
+        self.request();
+        let frame_state = self
+            .frame_states
+            .lock()
+            .unwrap()
+            .recv()
+            .unwrap_or(Err(xr::sys::Result::ERROR_RUNTIME_FAILURE));
+        self.pending.store(false, Ordering::Release);
+        frame_state
+    }
+}
 
 pub enum Swapchain {
     Vulkan(SwapchainInner<xr::Vulkan>),
@@ -69,6 +130,18 @@ impl Swapchain {
             ),
         }
     }
+
+    pub(crate) fn end_without_layers(
+        &self,
+        predicted_display_time: xr::Time,
+        environment_blend_mode: xr::EnvironmentBlendMode,
+    ) -> xr::Result<()> {
+        match self {
+            Swapchain::Vulkan(swapchain) => {
+                swapchain.end_without_layers(predicted_display_time, environment_blend_mode)
+            }
+        }
+    }
 }
 
 pub struct SwapchainInner<G: xr::Graphics> {
@@ -133,11 +206,11 @@ impl<G: xr::Graphics> SwapchainInner<G> {
                 height: resolution.y as _,
             },
         };
-        let swapchain = self.handle.lock().unwrap();
-        if views.len() == 0 {
-            warn!("views are len of 0");
-            return Ok(());
+        if views.len() < 2 {
+            warn!("views are len of {}", views.len());
+            return self.end_without_layers(predicted_display_time, environment_blend_mode);
         }
+        let swapchain = self.handle.lock().unwrap();
         self.stream.lock().unwrap().end(
             predicted_display_time,
             environment_blend_mode,
@@ -163,4 +236,16 @@ impl<G: xr::Graphics> SwapchainInner<G> {
             ])],
         )
     }
+
+    /// ends a frame that shows nothing, for frames that were begun but not rendered
+    fn end_without_layers(
+        &self,
+        predicted_display_time: xr::Time,
+        environment_blend_mode: xr::EnvironmentBlendMode,
+    ) -> xr::Result<()> {
+        self.stream
+            .lock()
+            .unwrap()
+            .end(predicted_display_time, environment_blend_mode, &[])
+    }
 }
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::ptr;
+
+use bevy::prelude::*;
+use openxr as xr;
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::oculus_touch::ActionSets;
+
+/// Bindings suggested for the actions of all action sets.
+///
+/// Suggesting bindings for an interaction profile replaces the earlier suggestions for it, so
+/// everything that creates actions adds its bindings here and they are suggested together by
+/// [`attach_action_sets`].
+#[derive(Resource, Default)]
+pub struct XrSuggestedBindings {
+    bindings: HashMap<String, Vec<(xr::sys::Action, String)>>,
+}
+
+impl XrSuggestedBindings {
+    /// binds `action` to a full input path, e.g. `/user/hand/left/input/x/click`
+    pub fn add<T: xr::ActionTy>(&mut self, profile: &str, action: &xr::Action<T>, path: &str) {
+        self.bindings
+            .entry(profile.to_string())
+            .or_default()
+            .push((action.as_raw(), path.to_string()));
+    }
+
+    /// binds `action` for both hands, paths starting with `/user/` are used as they are
+    /// and everything else is relative to the hand, e.g. `input/trigger/value`
+    pub fn add_handed<T: xr::ActionTy>(
+        &mut self,
+        profile: &str,
+        action: &xr::Action<T>,
+        path: &str,
+    ) {
+        if path.starts_with("/user/") {
+            self.add(profile, action, path);
+            return;
+        }
+        for user_path in ["/user/hand/left", "/user/hand/right"] {
+            self.add(profile, action, &format!("{}/{}", user_path, path));
+        }
+    }
+
+    /// the interaction profiles that have bindings
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.bindings.keys().map(|profile| profile.as_str())
+    }
+
+    fn suggest_profile(
+        &self,
+        instance: &xr::Instance,
+        profile: &str,
+        bindings: &[(xr::sys::Action, String)],
+    ) -> xr::Result<()> {
+        let suggested = bindings
+            .iter()
+            .map(|(action, path)| {
+                Ok(xr::sys::ActionSuggestedBinding {
+                    action: *action,
+                    binding: instance.string_to_path(path)?,
+                })
+            })
+            .collect::<xr::Result<Vec<_>>>()?;
+        let info = xr::sys::InteractionProfileSuggestedBinding {
+            ty: xr::sys::InteractionProfileSuggestedBinding::TYPE,
+            next: ptr::null(),
+            interaction_profile: instance.string_to_path(profile)?,
+            count_suggested_bindings: suggested.len() as u32,
+            suggested_bindings: suggested.as_ptr(),
+        };
+        let result = unsafe {
+            (instance.fp().suggest_interaction_profile_bindings)(instance.as_raw(), &info)
+        };
+        match result.into_raw() >= 0 {
+            true => Ok(()),
+            false => Err(result),
+        }
+    }
+}
+
+/// Suggests all [`XrSuggestedBindings`] and attaches the [`ActionSets`] to the session.
+///
+/// Action sets can only be attached once, so systems creating actions at startup have to run
+/// before this.
+pub fn attach_action_sets(
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    action_sets: Res<ActionSets>,
+    bindings: Res<XrSuggestedBindings>,
+) {
+    for (profile, profile_bindings) in bindings.bindings.iter() {
+        //a profile the runtime doesn't know shouldn't break the other ones
+        if let Err(err) = bindings.suggest_profile(&instance, profile, profile_bindings) {
+            warn!("unable to suggest bindings for {}: {}", profile, err);
+        }
+    }
+    if let Err(err) = session.attach_action_sets(&action_sets.0.iter().collect::<Vec<_>>()) {
+        error!("unable to attach action sets: {}", err);
+    }
+}
@@ -54,42 +54,44 @@ pub fn draw_gizmos(
         Without<OpenXRLeftController>,
         Without<OpenXRTrackingRoot>,
     )>,
-    hand_tracking: Res<HandTrackingTracker>,
+    hand_tracking: Option<Res<HandTrackingTracker>>,
 ) {
-    let handtracking_ref = hand_tracking.get_ref(&xr_input, &frame_state);
-    if let Some(joints) = handtracking_ref.get_left_poses() {
-        for joint in joints {
-            let p = joint.pose.position;
-            let r = joint.pose.orientation;
-            let quat = r.to_quat();
-            let trans = Transform::from_rotation(quat);
-            gizmos.circle(
-                (p.x, p.y, p.z).into(),
-                trans.forward(),
-                joint.radius,
-                Color::ORANGE_RED,
-            );
+    if let Some(hand_tracking) = hand_tracking {
+        let handtracking_ref = hand_tracking.get_ref(&xr_input, &frame_state);
+        if let Ok(Some(joints)) = handtracking_ref.get_left_poses() {
+            for joint in joints {
+                let p = joint.pose.position;
+                let r = joint.pose.orientation;
+                let quat = r.to_quat();
+                let trans = Transform::from_rotation(quat);
+                gizmos.circle(
+                    (p.x, p.y, p.z).into(),
+                    trans.forward(),
+                    joint.radius,
+                    Color::ORANGE_RED,
+                );
+            }
+        } else {
+            info!("left_hand_poses returned None");
         }
-    } else {
-        info!("left_hand_poses returned None");
-    }
-    if let Some(joints) = handtracking_ref.get_right_poses() {
-        for joint in joints {
-            let p = joint.pose.position;
-            let r = joint.pose.orientation;
-            let quat = r.to_quat();
-            let trans = Transform::from_rotation(quat);
-            gizmos.circle(
-                (p.x, p.y, p.z).into(),
-                trans.forward(),
-                joint.radius,
-                Color::LIME_GREEN,
-            );
+        if let Ok(Some(joints)) = handtracking_ref.get_right_poses() {
+            for joint in joints {
+                let p = joint.pose.position;
+                let r = joint.pose.orientation;
+                let quat = r.to_quat();
+                let trans = Transform::from_rotation(quat);
+                gizmos.circle(
+                    (p.x, p.y, p.z).into(),
+                    trans.forward(),
+                    joint.radius,
+                    Color::LIME_GREEN,
+                );
+            }
+            return;
         }
-        return;
     }
-    //lock frame
-    let frame_state = *frame_state.lock().unwrap();
+    //get frame state
+    let frame_state = **frame_state;
     //get controller
     let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
     //tracking root?
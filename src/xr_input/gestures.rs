@@ -0,0 +1,317 @@
+use bevy::prelude::*;
+
+use super::{
+    hand::{update_hand_skeletons, HandBone, HandJoints, OpenXRHand},
+    Hand,
+};
+
+/// Recognizes gestures from the hand bone entities, so it works the same for tracked and
+/// emulated hands. Adds the gesture components to every [`OpenXRHand`].
+#[derive(Default)]
+pub struct HandGesturePlugin;
+
+impl Plugin for HandGesturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandGestureConfig>()
+            .add_event::<HandGestureChanged>()
+            .add_systems(
+                Update,
+                (insert_gesture_components, update_hand_gestures)
+                    .chain()
+                    .after(update_hand_skeletons),
+            );
+    }
+}
+
+/// Thresholds of the gesture recognition
+#[derive(Resource, Clone, Debug)]
+pub struct HandGestureConfig {
+    /// distance between thumb and index tip in meters at which the pinch strength is 1
+    pub pinch_closed_distance: f32,
+    /// distance between thumb and index tip in meters at which the pinch strength is 0
+    pub pinch_open_distance: f32,
+    /// a gesture starts once its score rises above this
+    pub enter_threshold: f32,
+    /// an active gesture ends once its score falls below this, lower than `enter_threshold`
+    /// so gestures don't flicker on the edge
+    pub exit_threshold: f32,
+}
+
+impl Default for HandGestureConfig {
+    fn default() -> Self {
+        Self {
+            pinch_closed_distance: 0.015,
+            pinch_open_distance: 0.08,
+            enter_threshold: 0.8,
+            exit_threshold: 0.6,
+        }
+    }
+}
+
+/// How close thumb and index tip are, from 0 (apart) to 1 (touching)
+#[derive(Component, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct PinchStrength(pub f32);
+
+/// How closed the hand is, the average curl of the index, middle, ring and little finger
+#[derive(Component, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct GrabStrength(pub f32);
+
+/// Curl of every finger from 0 (straight) to 1 (fully curled)
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct FingerCurls {
+    pub thumb: f32,
+    pub index: f32,
+    pub middle: f32,
+    pub ring: f32,
+    pub little: f32,
+}
+
+/// Angles in radians between neighbouring fingers, measured in the plane of the palm
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct FingerSplay {
+    pub thumb_index: f32,
+    pub index_middle: f32,
+    pub middle_ring: f32,
+    pub ring_little: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HandGesture {
+    Pinch,
+    Fist,
+    Point,
+    OpenPalm,
+    ThumbsUp,
+}
+
+impl HandGesture {
+    pub const fn all() -> [HandGesture; 5] {
+        [
+            HandGesture::Pinch,
+            HandGesture::Fist,
+            HandGesture::Point,
+            HandGesture::OpenPalm,
+            HandGesture::ThumbsUp,
+        ]
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            HandGesture::Pinch => 0,
+            HandGesture::Fist => 1,
+            HandGesture::Point => 2,
+            HandGesture::OpenPalm => 3,
+            HandGesture::ThumbsUp => 4,
+        }
+    }
+}
+
+/// The discrete gestures a hand is currently making
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct HandGestures {
+    active: [bool; 5],
+}
+
+impl HandGestures {
+    pub fn is_active(&self, gesture: HandGesture) -> bool {
+        self.active[gesture.index()]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = HandGesture> + '_ {
+        HandGesture::all()
+            .into_iter()
+            .filter(|gesture| self.is_active(*gesture))
+    }
+}
+
+/// Sent when a hand starts or stops making a gesture
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HandGestureChanged {
+    pub entity: Entity,
+    pub hand: Hand,
+    pub gesture: HandGesture,
+    pub active: bool,
+}
+
+#[derive(Bundle, Default)]
+pub struct HandGestureBundle {
+    pub pinch_strength: PinchStrength,
+    pub grab_strength: GrabStrength,
+    pub finger_curls: FingerCurls,
+    pub finger_splay: FingerSplay,
+    pub gestures: HandGestures,
+}
+
+// sum of the bend angles of a fully curled finger, in radians
+const FINGER_CURL_MAX: f32 = 4.5;
+const THUMB_CURL_MAX: f32 = 2.4;
+
+pub fn insert_gesture_components(
+    mut commands: Commands,
+    hands: Query<Entity, (With<OpenXRHand>, Without<HandGestures>)>,
+) {
+    for entity in hands.iter() {
+        commands.entity(entity).insert(HandGestureBundle::default());
+    }
+}
+
+pub fn update_hand_gestures(
+    config: Res<HandGestureConfig>,
+    hand_joints: HandJoints,
+    mut hand_query: Query<
+        (
+            Entity,
+            &Hand,
+            &mut PinchStrength,
+            &mut GrabStrength,
+            &mut FingerCurls,
+            &mut FingerSplay,
+            &mut HandGestures,
+        ),
+        With<OpenXRHand>,
+    >,
+    mut gesture_events: EventWriter<HandGestureChanged>,
+) {
+    for (entity, hand, mut pinch, mut grab, mut curls, mut splay, mut gestures) in
+        hand_query.iter_mut()
+    {
+        let joints = match hand_joints.transforms(*hand) {
+            Some(joints) => joints,
+            None => continue,
+        };
+        let joints = &joints;
+        let position = |bone: HandBone| joints[bone.get_index_from_bone()].translation;
+
+        let pinch_distance = position(HandBone::ThumbTip).distance(position(HandBone::IndexTip));
+        pinch.0 = 1.0
+            - ((pinch_distance - config.pinch_closed_distance)
+                / (config.pinch_open_distance - config.pinch_closed_distance))
+                .clamp(0.0, 1.0);
+
+        *curls = FingerCurls {
+            thumb: finger_curl(joints, &THUMB, THUMB_CURL_MAX),
+            index: finger_curl(joints, &INDEX, FINGER_CURL_MAX),
+            middle: finger_curl(joints, &MIDDLE, FINGER_CURL_MAX),
+            ring: finger_curl(joints, &RING, FINGER_CURL_MAX),
+            little: finger_curl(joints, &LITTLE, FINGER_CURL_MAX),
+        };
+        grab.0 = (curls.index + curls.middle + curls.ring + curls.little) / 4.0;
+
+        //the back of the hand is +Y of the palm joint
+        let palm_normal = joints[HandBone::Palm.get_index_from_bone()].up();
+        let direction = |finger: &[HandBone]| {
+            let dir = position(finger[finger.len() - 1]) - position(finger[1]);
+            (dir - palm_normal * dir.dot(palm_normal)).normalize_or_zero()
+        };
+        let (thumb, index, middle, ring, little) = (
+            direction(&THUMB),
+            direction(&INDEX),
+            direction(&MIDDLE),
+            direction(&RING),
+            direction(&LITTLE),
+        );
+        *splay = FingerSplay {
+            thumb_index: angle_between(thumb, index),
+            index_middle: angle_between(index, middle),
+            middle_ring: angle_between(middle, ring),
+            ring_little: angle_between(ring, little),
+        };
+
+        let thumb_up = (position(HandBone::ThumbTip) - position(HandBone::ThumbProximal))
+            .normalize_or_zero()
+            .dot(Vec3::Y)
+            .max(0.0);
+        let others_curled = curls.middle.min(curls.ring).min(curls.little);
+        for gesture in HandGesture::all() {
+            let score = match gesture {
+                HandGesture::Pinch => pinch.0,
+                HandGesture::Fist => others_curled.min(curls.index),
+                HandGesture::Point => others_curled.min(1.0 - curls.index),
+                HandGesture::OpenPalm => {
+                    1.0 - curls
+                        .thumb
+                        .max(curls.index)
+                        .max(curls.middle)
+                        .max(curls.ring)
+                        .max(curls.little)
+                }
+                HandGesture::ThumbsUp => others_curled
+                    .min(curls.index)
+                    .min(1.0 - curls.thumb)
+                    .min(thumb_up),
+            };
+            let was_active = gestures.is_active(gesture);
+            let active = match was_active {
+                true => score > config.exit_threshold,
+                false => score > config.enter_threshold,
+            };
+            if active != was_active {
+                gestures.active[gesture.index()] = active;
+                gesture_events.send(HandGestureChanged {
+                    entity,
+                    hand: *hand,
+                    gesture,
+                    active,
+                });
+            }
+        }
+    }
+}
+
+const THUMB: [HandBone; 4] = [
+    HandBone::ThumbMetacarpal,
+    HandBone::ThumbProximal,
+    HandBone::ThumbDistal,
+    HandBone::ThumbTip,
+];
+const INDEX: [HandBone; 5] = [
+    HandBone::IndexMetacarpal,
+    HandBone::IndexProximal,
+    HandBone::IndexIntermediate,
+    HandBone::IndexDistal,
+    HandBone::IndexTip,
+];
+const MIDDLE: [HandBone; 5] = [
+    HandBone::MiddleMetacarpal,
+    HandBone::MiddleProximal,
+    HandBone::MiddleIntermediate,
+    HandBone::MiddleDistal,
+    HandBone::MiddleTip,
+];
+const RING: [HandBone; 5] = [
+    HandBone::RingMetacarpal,
+    HandBone::RingProximal,
+    HandBone::RingIntermediate,
+    HandBone::RingDistal,
+    HandBone::RingTip,
+];
+const LITTLE: [HandBone; 5] = [
+    HandBone::LittleMetacarpal,
+    HandBone::LittleProximal,
+    HandBone::LittleIntermediate,
+    HandBone::LittleDistal,
+    HandBone::LittleTip,
+];
+
+/// sums up the bend between the segments of a finger, from the metacarpal to the tip
+fn finger_curl(joints: &[Transform; 26], finger: &[HandBone], max_angle: f32) -> f32 {
+    let segments: Vec<Vec3> = finger
+        .windows(2)
+        .map(|pair| {
+            joints[pair[1].get_index_from_bone()].translation
+                - joints[pair[0].get_index_from_bone()].translation
+        })
+        .collect();
+    let bend: f32 = segments
+        .windows(2)
+        .map(|pair| angle_between(pair[0], pair[1]))
+        .sum();
+    (bend / max_angle).clamp(0.0, 1.0)
+}
+
+fn angle_between(a: Vec3, b: Vec3) -> f32 {
+    if a == Vec3::ZERO || b == Vec3::ZERO {
+        return 0.0;
+    }
+    a.angle_between(b)
+}
@@ -0,0 +1,385 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::interactions::{SelectEntered, SelectExited};
+
+/// Makes an interactable follow the interactors selecting it.
+///
+/// Grabbing with two interactors needs
+/// [`XrSelectMode::Multiple`](super::interactions::XrSelectMode) on the interactable.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XrGrabbable {
+    pub attach_mode: XrAttachMode,
+    pub movement: XrGrabMovement,
+    /// the point of the interactable that is held, relative to it. Only used by
+    /// [`XrAttachMode::Snap`]
+    pub attach_point: Transform,
+    /// a second interactor rotates and scales the interactable instead of being ignored
+    pub two_handed: bool,
+    /// multiplies the velocity the interactable is released with, 0 drops it
+    pub throw_velocity_scale: f32,
+}
+
+impl Default for XrGrabbable {
+    fn default() -> Self {
+        Self {
+            attach_mode: XrAttachMode::KeepOffset,
+            movement: XrGrabMovement::Transform,
+            attach_point: Transform::IDENTITY,
+            two_handed: false,
+            throw_velocity_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrAttachMode {
+    /// moves the attach point onto the interactor
+    Snap,
+    /// keeps the offset the interactable had to the interactor when it was grabbed
+    KeepOffset,
+}
+
+/// How a held interactable is moved
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrGrabMovement {
+    /// sets the transform directly
+    Transform,
+    /// turns a physics body kinematic while it's held and sets the transform
+    Kinematic,
+    /// keeps a physics body dynamic and only sets its velocity, so it collides with the world
+    /// while it's held. The velocity is written to [`XrGrabVelocity`]
+    VelocityTracking,
+}
+
+/// The velocity a held interactable needs to reach its target by the next frame
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct XrGrabVelocity {
+    pub linear: Vec3,
+    /// axis scaled by radians per second
+    pub angular: Vec3,
+}
+
+/// Sent when the last interactor lets go of an [`XrGrabbable`], with the velocity it was thrown
+/// with
+#[derive(Event, Clone, Copy, Debug)]
+pub struct XrGrabReleased {
+    pub grabbable: Entity,
+    pub linear_velocity: Vec3,
+    pub angular_velocity: Vec3,
+}
+
+/// Which interactors hold an [`XrGrabbable`], added to it automatically
+#[derive(Component, Clone, Debug, Default)]
+pub struct XrGrabState {
+    holders: Vec<GrabHolder>,
+    two_handed_start: Option<TwoHandedStart>,
+    // recent targets for the throw velocity
+    history: VecDeque<(f32, Transform)>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct GrabHolder {
+    interactor: Entity,
+    // the grabbable relative to the interactor
+    offset: Transform,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TwoHandedStart {
+    midpoint: Vec3,
+    direction: Vec3,
+    distance: f32,
+    transform: Transform,
+}
+
+impl XrGrabState {
+    pub fn is_grabbed(&self) -> bool {
+        !self.holders.is_empty()
+    }
+
+    /// the interactors holding it, the first one moves it
+    pub fn holders(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.holders.iter().map(|holder| holder.interactor)
+    }
+}
+
+// how long the targets are kept for the throw velocity, in seconds
+const THROW_HISTORY: f32 = 0.1;
+
+pub fn insert_grab_states(
+    mut commands: Commands,
+    grabbables: Query<Entity, (Added<XrGrabbable>, Without<XrGrabState>)>,
+) {
+    for entity in grabbables.iter() {
+        commands
+            .entity(entity)
+            .insert((XrGrabState::default(), XrGrabVelocity::default()));
+    }
+}
+
+pub fn update_grab_holders(
+    time: Res<Time>,
+    mut select_entered: EventReader<SelectEntered>,
+    mut select_exited: EventReader<SelectExited>,
+    mut grabbables: Query<(
+        &XrGrabbable,
+        &mut XrGrabState,
+        &GlobalTransform,
+        &mut XrGrabVelocity,
+    )>,
+    interactors: Query<&GlobalTransform, Without<XrGrabbable>>,
+    mut released: EventWriter<XrGrabReleased>,
+) {
+    for event in select_exited.read() {
+        let (grabbable, mut state, transform, mut velocity) =
+            match grabbables.get_mut(event.interactable) {
+                Ok(grabbable) => grabbable,
+                Err(_) => continue,
+            };
+        state
+            .holders
+            .retain(|holder| holder.interactor != event.interactor);
+        state.two_handed_start = None;
+        match state.holders.first_mut() {
+            //the other hand keeps holding it where it is instead of snapping
+            Some(holder) => {
+                if let Ok(interactor) = interactors.get(holder.interactor) {
+                    holder.offset = transform.reparented_to(interactor);
+                }
+            }
+            None => {
+                let (linear_velocity, angular_velocity) = throw_velocity(&state.history);
+                state.history.clear();
+                *velocity = XrGrabVelocity::default();
+                released.send(XrGrabReleased {
+                    grabbable: event.interactable,
+                    linear_velocity: linear_velocity * grabbable.throw_velocity_scale,
+                    angular_velocity: angular_velocity * grabbable.throw_velocity_scale,
+                });
+            }
+        }
+    }
+    for event in select_entered.read() {
+        let (grabbable, mut state, transform, _) = match grabbables.get_mut(event.interactable) {
+            Ok(grabbable) => grabbable,
+            Err(_) => continue,
+        };
+        let interactor = match interactors.get(event.interactor) {
+            Ok(interactor) => interactor,
+            Err(_) => continue,
+        };
+        let offset = match grabbable.attach_mode {
+            XrAttachMode::Snap => {
+                Transform::from_matrix(grabbable.attach_point.compute_matrix().inverse())
+            }
+            XrAttachMode::KeepOffset => transform.reparented_to(interactor),
+        };
+        state.holders.push(GrabHolder {
+            interactor: event.interactor,
+            offset,
+        });
+        if grabbable.two_handed && state.holders.len() == 2 {
+            state.two_handed_start =
+                two_handed_points(&state.holders, &interactors).map(|(first, second)| {
+                    TwoHandedStart {
+                        midpoint: first.lerp(second, 0.5),
+                        direction: (second - first).normalize_or_zero(),
+                        distance: first.distance(second),
+                        transform: transform.compute_transform(),
+                    }
+                });
+        }
+        state.history.clear();
+        state
+            .history
+            .push_back((time.elapsed_seconds(), transform.compute_transform()));
+    }
+}
+
+pub fn move_grabbables(
+    time: Res<Time>,
+    mut grabbables: Query<(
+        &XrGrabbable,
+        &mut XrGrabState,
+        &mut XrGrabVelocity,
+        &mut Transform,
+        &GlobalTransform,
+        Option<&Parent>,
+    )>,
+    interactors: Query<&GlobalTransform, Without<XrGrabbable>>,
+) {
+    let now = time.elapsed_seconds();
+    let delta = time.delta_seconds();
+    for (grabbable, mut state, mut velocity, mut transform, global_transform, parent) in
+        grabbables.iter_mut()
+    {
+        let holder = match state.holders.first() {
+            Some(holder) => *holder,
+            None => continue,
+        };
+        let target = match (
+            state.two_handed_start,
+            two_handed_points(&state.holders, &interactors),
+        ) {
+            (Some(start), Some((first, second))) => {
+                let midpoint = first.lerp(second, 0.5);
+                let rotation =
+                    Quat::from_rotation_arc(start.direction, (second - first).normalize_or_zero());
+                let scale = match start.distance > f32::EPSILON {
+                    true => first.distance(second) / start.distance,
+                    false => 1.0,
+                };
+                Transform {
+                    translation: midpoint
+                        + rotation * ((start.transform.translation - start.midpoint) * scale),
+                    rotation: rotation * start.transform.rotation,
+                    scale: start.transform.scale * scale,
+                }
+            }
+            _ => match interactors.get(holder.interactor) {
+                Ok(interactor) => interactor.mul_transform(holder.offset).compute_transform(),
+                Err(_) => continue,
+            },
+        };
+
+        state.history.push_back((now, target));
+        while state
+            .history
+            .front()
+            .map_or(false, |(time, _)| now - *time > THROW_HISTORY)
+        {
+            state.history.pop_front();
+        }
+
+        match grabbable.movement {
+            XrGrabMovement::Transform | XrGrabMovement::Kinematic => {
+                *transform = match parent.and_then(|parent| interactors.get(parent.get()).ok()) {
+                    Some(parent) => GlobalTransform::from(target).reparented_to(parent),
+                    None => target,
+                };
+            }
+            XrGrabMovement::VelocityTracking => {
+                if delta <= 0.0 {
+                    continue;
+                }
+                let current = global_transform.compute_transform();
+                velocity.linear = (target.translation - current.translation) / delta;
+                velocity.angular = angular_velocity(current.rotation, target.rotation, delta);
+            }
+        }
+    }
+}
+
+fn two_handed_points(
+    holders: &[GrabHolder],
+    interactors: &Query<&GlobalTransform, Without<XrGrabbable>>,
+) -> Option<(Vec3, Vec3)> {
+    match holders {
+        [first, second, ..] => Some((
+            interactors.get(first.interactor).ok()?.translation(),
+            interactors.get(second.interactor).ok()?.translation(),
+        )),
+        _ => None,
+    }
+}
+
+fn throw_velocity(history: &VecDeque<(f32, Transform)>) -> (Vec3, Vec3) {
+    match (history.front(), history.back()) {
+        (Some((start_time, start)), Some((end_time, end))) if end_time > start_time => {
+            let duration = end_time - start_time;
+            (
+                (end.translation - start.translation) / duration,
+                angular_velocity(start.rotation, end.rotation, duration),
+            )
+        }
+        _ => (Vec3::ZERO, Vec3::ZERO),
+    }
+}
+
+fn angular_velocity(from: Quat, to: Quat, duration: f32) -> Vec3 {
+    let mut rotation = to * from.inverse();
+    //take the short way around
+    if rotation.w < 0.0 {
+        rotation = -rotation;
+    }
+    let (axis, angle) = rotation.to_axis_angle();
+    axis * angle / duration
+}
+
+#[cfg(feature = "rapier")]
+pub use self::rapier::*;
+
+#[cfg(feature = "rapier")]
+mod rapier {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::{RigidBody, Velocity};
+
+    use super::{XrGrabMovement, XrGrabReleased, XrGrabState, XrGrabVelocity, XrGrabbable};
+    use crate::xr_input::interactions::SelectEntered;
+
+    /// The body type a [`XrGrabMovement::Kinematic`] grabbable had before it was grabbed
+    #[derive(Component, Clone, Copy, Debug)]
+    pub struct XrGrabbedRigidBody(pub RigidBody);
+
+    /// Applies grabbing and throwing to rapier bodies
+    pub fn apply_grabs_to_rapier_bodies(
+        mut commands: Commands,
+        mut select_entered: EventReader<SelectEntered>,
+        mut released: EventReader<XrGrabReleased>,
+        mut bodies: Query<(
+            &XrGrabbable,
+            &XrGrabState,
+            &XrGrabVelocity,
+            &mut RigidBody,
+            Option<&mut Velocity>,
+            Option<&XrGrabbedRigidBody>,
+        )>,
+    ) {
+        for event in select_entered.read() {
+            if let Ok((grabbable, _, _, mut body, _, None)) = bodies.get_mut(event.interactable) {
+                if grabbable.movement == XrGrabMovement::Kinematic {
+                    commands
+                        .entity(event.interactable)
+                        .insert(XrGrabbedRigidBody(*body));
+                    *body = RigidBody::KinematicPositionBased;
+                }
+            }
+        }
+        for event in released.read() {
+            let (_, _, _, mut body, velocity, grabbed_body) = match bodies.get_mut(event.grabbable)
+            {
+                Ok(body) => body,
+                Err(_) => continue,
+            };
+            if let Some(grabbed_body) = grabbed_body {
+                *body = grabbed_body.0;
+                commands
+                    .entity(event.grabbable)
+                    .remove::<XrGrabbedRigidBody>();
+            }
+            match velocity {
+                Some(mut velocity) => {
+                    velocity.linvel = event.linear_velocity;
+                    velocity.angvel = event.angular_velocity;
+                }
+                None => {
+                    commands.entity(event.grabbable).insert(Velocity {
+                        linvel: event.linear_velocity,
+                        angvel: event.angular_velocity,
+                    });
+                }
+            }
+        }
+        for (grabbable, state, grab_velocity, _, velocity, _) in bodies.iter_mut() {
+            match (grabbable.movement, velocity) {
+                (XrGrabMovement::VelocityTracking, Some(mut velocity)) if state.is_grabbed() => {
+                    velocity.linvel = grab_velocity.linear;
+                    velocity.angvel = grab_velocity.angular;
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -1,24 +1,36 @@
 use std::f32::consts::PI;
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::{
-    default, info, Color, Commands, Component, Deref, DerefMut, Entity, Gizmos, GlobalTransform,
-    Plugin, PostUpdate, PreUpdate, Quat, Query, Res, ResMut, Resource, SpatialBundle, Startup,
-    Transform, Update, Vec3, With, Without,
+    default, info, Assets, BuildChildren, Color, Commands, Component, Deref, DerefMut, Entity,
+    Event, EventReader, EventWriter, Gizmos, GlobalTransform, IntoSystemConfigs, Local, Plugin,
+    PostUpdate, PreUpdate, Quat, Query, Res, Resource, SpatialBundle, Startup, Transform, Update,
+    Vec3, With, Without,
 };
+use openxr as xr;
 use openxr::{HandJoint, Posef};
 
 use crate::{
-    input::XrInput,
+    error::XrErrorEvent,
+    input::{pose_to_transform, XrInput, XrInteractionProfileChanged},
     resources::{XrFrameState, XrInstance, XrSession},
     xr_input::Vec3Conv,
+    XrFrameSet,
 };
 
 use super::{
+    action_set_system,
+    actions::attach_action_sets,
+    hand_emulation::{
+        blend_hand_poses, mirror_transform, pose_in_grip_space, setup_emulated_hand_actions,
+        update_emulated_finger_curls, EmulatedFingerCurls, EmulatedHandConfig, Finger,
+    },
+    hand_pose_matching::HandPose,
     hand_poses::get_simulated_open_hand_transforms,
-    handtracking::HandTrackingTracker,
-    oculus_touch::OculusController,
-    trackers::{OpenXRLeftController, OpenXRRightController, OpenXRTracker, OpenXRTrackingRoot},
-    Hand, QuatConv,
+    handtracking::{HandJointsMotionRange, HandTrackingTracker},
+    oculus_touch::{setup_oculus_controller, OculusController},
+    trackers::{OpenXRLeftController, OpenXRRightController, OpenXRTracker},
+    Hand,
 };
 
 /// add debug renderer for controllers
@@ -27,11 +39,34 @@ pub struct OpenXrHandInput;
 
 impl Plugin for OpenXrHandInput {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.add_systems(Update, update_hand_skeletons)
-            .add_systems(PreUpdate, update_hand_states)
-            .add_systems(Startup, spawn_hand_entities)
-            .insert_resource(HandStatesResource::default())
-            .insert_resource(HandInputSource::default());
+        app.add_systems(Update, (update_hand_skeletons, update_hand_rigs).chain())
+            .add_systems(
+                PostUpdate,
+                (update_hand_skeletons, update_hand_rigs)
+                    .chain()
+                    .in_set(XrFrameSet::LateLatch),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    update_hand_input_sources,
+                    //read the actions after they were synced for this frame
+                    update_emulated_finger_curls.after(action_set_system),
+                )
+                    .in_set(XrFrameSet::LocatePoses),
+            )
+            .add_systems(
+                Startup,
+                (
+                    spawn_hand_entities,
+                    setup_emulated_hand_actions
+                        .after(setup_oculus_controller)
+                        .before(attach_action_sets),
+                ),
+            )
+            .add_event::<HandInputSourceChanged>()
+            .init_resource::<EmulatedHandConfig>()
+            .init_resource::<HandInputSourceMode>();
     }
 }
 
@@ -45,9 +80,12 @@ impl Plugin for HandInputDebugRenderer {
     }
 }
 
-#[derive(Resource)]
+/// Where the skeleton of a hand comes from, one per [`OpenXRHand`]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum HandInputSource {
+    /// posed from the controller buttons
     Emulated,
+    /// tracked joints from `XR_EXT_hand_tracking`
     OpenXr,
 }
 
@@ -57,214 +95,142 @@ impl Default for HandInputSource {
     }
 }
 
-#[derive(Resource, Default, Clone, Copy)]
-pub struct HandsResource {
-    pub left: HandResource,
-    pub right: HandResource,
-}
-#[derive(Clone, Copy)]
-pub struct HandResource {
-    pub palm: Entity,
-    pub wrist: Entity,
-    pub thumb: ThumbResource,
-    pub index: IndexResource,
-    pub middle: MiddleResource,
-    pub ring: RingResource,
-    pub little: LittleResource,
+/// How the [`HandInputSource`] of each hand is chosen
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandInputSourceMode {
+    /// switch per hand between tracked hands and controllers as the user puts them down
+    Automatic,
+    /// use the same source for both hands
+    Fixed(HandInputSource),
 }
 
-impl Default for HandResource {
+impl Default for HandInputSourceMode {
     fn default() -> Self {
-        Self {
-            palm: Entity::PLACEHOLDER,
-            wrist: Entity::PLACEHOLDER,
-            thumb: Default::default(),
-            index: Default::default(),
-            middle: Default::default(),
-            ring: Default::default(),
-            little: Default::default(),
-        }
+        HandInputSourceMode::Automatic
     }
 }
-#[derive(Clone, Copy)]
-pub struct ThumbResource {
-    pub metacarpal: Entity,
-    pub proximal: Entity,
-    pub distal: Entity,
-    pub tip: Entity,
-}
 
-impl Default for ThumbResource {
-    fn default() -> Self {
-        Self {
-            metacarpal: Entity::PLACEHOLDER,
-            proximal: Entity::PLACEHOLDER,
-            distal: Entity::PLACEHOLDER,
-            tip: Entity::PLACEHOLDER,
-        }
-    }
+/// One entity per hand that carries its [`HandInputSource`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct OpenXRHand;
+
+/// What the runtime currently reports for a hand, used to pick its [`HandInputSource`]
+#[derive(Component, Clone, Debug, Default)]
+pub struct HandInputActivity {
+    /// the hand joints are tracked
+    pub joints_active: bool,
+    /// the controller grip pose is tracked
+    pub controller_tracked: bool,
+    /// the interaction profile bound to this hand, `None` when no controller is bound
+    pub interaction_profile: Option<String>,
 }
-#[derive(Clone, Copy)]
-pub struct IndexResource {
-    pub metacarpal: Entity,
-    pub proximal: Entity,
-    pub intermediate: Entity,
-    pub distal: Entity,
-    pub tip: Entity,
+
+/// Sent when a hand switches between tracked joints and a controller
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HandInputSourceChanged {
+    pub entity: Entity,
+    pub hand: Hand,
+    pub previous: HandInputSource,
+    pub source: HandInputSource,
 }
 
-impl Default for IndexResource {
-    fn default() -> Self {
-        Self {
-            metacarpal: Entity::PLACEHOLDER,
-            proximal: Entity::PLACEHOLDER,
-            intermediate: Entity::PLACEHOLDER,
-            distal: Entity::PLACEHOLDER,
-            tip: Entity::PLACEHOLDER,
-        }
+/// The joint entities of a hand, in the order of [`HandBone::get_all_bones`]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HandJointEntities(pub [Entity; 26]);
+
+impl HandJointEntities {
+    pub fn get(&self, bone: HandBone) -> Entity {
+        self.0[bone.get_index_from_bone()]
     }
 }
-#[derive(Clone, Copy)]
-pub struct MiddleResource {
-    pub metacarpal: Entity,
-    pub proximal: Entity,
-    pub intermediate: Entity,
-    pub distal: Entity,
-    pub tip: Entity,
+
+/// A hand rig that copies the joints of the [`OpenXRHand`] of `source`.
+///
+/// With `mirror` the joints are mirrored on the YZ plane of the rig entity, which turns them
+/// into the other hand, e.g. for an avatar in a mirror. Spawn it with [`spawn_hand_rig`].
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HandRig {
+    pub source: Hand,
+    pub mirror: bool,
 }
-impl Default for MiddleResource {
-    fn default() -> Self {
-        Self {
-            metacarpal: Entity::PLACEHOLDER,
-            proximal: Entity::PLACEHOLDER,
-            intermediate: Entity::PLACEHOLDER,
-            distal: Entity::PLACEHOLDER,
-            tip: Entity::PLACEHOLDER,
-        }
+
+/// Spawns a hand root entity with the joints as a hierarchy below it, from the wrist over the
+/// metacarpals to the finger tips. Returns the root, which carries the [`HandJointEntities`].
+pub fn spawn_hand_rig(commands: &mut Commands, hand: Hand) -> Entity {
+    let root = commands.spawn((SpatialBundle::default(), hand)).id();
+    let bones = HandBone::get_all_bones();
+    let joints = bones.map(|bone| {
+        commands
+            .spawn((
+                SpatialBundle::default(),
+                bone,
+                hand,
+                HandBoneVelocity::default(),
+            ))
+            .id()
+    });
+    for bone in bones {
+        let parent = match bone.get_parent() {
+            Some(parent) => joints[parent.get_index_from_bone()],
+            None => root,
+        };
+        commands
+            .entity(parent)
+            .add_child(joints[bone.get_index_from_bone()]);
     }
+    commands.entity(root).insert(HandJointEntities(joints));
+    root
 }
-#[derive(Clone, Copy)]
-pub struct RingResource {
-    pub metacarpal: Entity,
-    pub proximal: Entity,
-    pub intermediate: Entity,
-    pub distal: Entity,
-    pub tip: Entity,
-}
-impl Default for RingResource {
-    fn default() -> Self {
-        Self {
-            metacarpal: Entity::PLACEHOLDER,
-            proximal: Entity::PLACEHOLDER,
-            intermediate: Entity::PLACEHOLDER,
-            distal: Entity::PLACEHOLDER,
-            tip: Entity::PLACEHOLDER,
-        }
+
+pub fn spawn_hand_entities(mut commands: Commands) {
+    for hand in [Hand::Left, Hand::Right] {
+        let root = spawn_hand_rig(&mut commands, hand);
+        commands.entity(root).insert((
+            OpenXRHand,
+            OpenXRTracker,
+            HandInputSource::Emulated,
+            HandInputActivity::default(),
+            EmulatedFingerCurls::default(),
+        ));
     }
 }
-#[derive(Clone, Copy)]
-pub struct LittleResource {
-    pub metacarpal: Entity,
-    pub proximal: Entity,
-    pub intermediate: Entity,
-    pub distal: Entity,
-    pub tip: Entity,
+
+/// Looks up the joints of the [`OpenXRHand`]s by [`Hand`] and [`HandBone`]
+#[derive(SystemParam)]
+pub struct HandJoints<'w, 's> {
+    hands: Query<'w, 's, (&'static Hand, &'static HandJointEntities), With<OpenXRHand>>,
+    joints: Query<'w, 's, &'static GlobalTransform, With<HandBone>>,
 }
-impl Default for LittleResource {
-    fn default() -> Self {
-        Self {
-            metacarpal: Entity::PLACEHOLDER,
-            proximal: Entity::PLACEHOLDER,
-            intermediate: Entity::PLACEHOLDER,
-            distal: Entity::PLACEHOLDER,
-            tip: Entity::PLACEHOLDER,
-        }
+
+impl<'w, 's> HandJoints<'w, 's> {
+    pub fn entities(&self, hand: Hand) -> Option<&HandJointEntities> {
+        self.hands
+            .iter()
+            .find(|(joint_hand, _)| **joint_hand == hand)
+            .map(|(_, joints)| joints)
     }
-}
 
-pub fn spawn_hand_entities(mut commands: Commands) {
-    let hands = [Hand::Left, Hand::Right];
-    let bones = HandBone::get_all_bones();
-    //hand resource
-    let mut hand_resource = HandsResource { ..default() };
-    for hand in hands.iter() {
-        for bone in bones.iter() {
-            let boneid = commands
-                .spawn((
-                    SpatialBundle::default(),
-                    bone.clone(),
-                    OpenXRTracker,
-                    hand.clone(),
-                ))
-                .id();
-            match hand {
-                Hand::Left => match bone {
-                    HandBone::Palm => hand_resource.left.palm = boneid,
-                    HandBone::Wrist => hand_resource.left.wrist = boneid,
-                    HandBone::ThumbMetacarpal => hand_resource.left.thumb.metacarpal = boneid,
-                    HandBone::ThumbProximal => hand_resource.left.thumb.proximal = boneid,
-                    HandBone::ThumbDistal => hand_resource.left.thumb.distal = boneid,
-                    HandBone::ThumbTip => hand_resource.left.thumb.tip = boneid,
-                    HandBone::IndexMetacarpal => hand_resource.left.index.metacarpal = boneid,
-                    HandBone::IndexProximal => hand_resource.left.index.proximal = boneid,
-                    HandBone::IndexIntermediate => hand_resource.left.index.intermediate = boneid,
-                    HandBone::IndexDistal => hand_resource.left.index.distal = boneid,
-                    HandBone::IndexTip => hand_resource.left.index.tip = boneid,
-                    HandBone::MiddleMetacarpal => hand_resource.left.middle.metacarpal = boneid,
-                    HandBone::MiddleProximal => hand_resource.left.middle.proximal = boneid,
-                    HandBone::MiddleIntermediate => hand_resource.left.middle.intermediate = boneid,
-                    HandBone::MiddleDistal => hand_resource.left.middle.distal = boneid,
-                    HandBone::MiddleTip => hand_resource.left.middle.tip = boneid,
-                    HandBone::RingMetacarpal => hand_resource.left.ring.metacarpal = boneid,
-                    HandBone::RingProximal => hand_resource.left.ring.proximal = boneid,
-                    HandBone::RingIntermediate => hand_resource.left.ring.intermediate = boneid,
-                    HandBone::RingDistal => hand_resource.left.ring.distal = boneid,
-                    HandBone::RingTip => hand_resource.left.ring.tip = boneid,
-                    HandBone::LittleMetacarpal => hand_resource.left.little.metacarpal = boneid,
-                    HandBone::LittleProximal => hand_resource.left.little.proximal = boneid,
-                    HandBone::LittleIntermediate => hand_resource.left.little.intermediate = boneid,
-                    HandBone::LittleDistal => hand_resource.left.little.distal = boneid,
-                    HandBone::LittleTip => hand_resource.left.little.tip = boneid,
-                },
-                Hand::Right => match bone {
-                    HandBone::Palm => hand_resource.right.palm = boneid,
-                    HandBone::Wrist => hand_resource.right.wrist = boneid,
-                    HandBone::ThumbMetacarpal => hand_resource.right.thumb.metacarpal = boneid,
-                    HandBone::ThumbProximal => hand_resource.right.thumb.proximal = boneid,
-                    HandBone::ThumbDistal => hand_resource.right.thumb.distal = boneid,
-                    HandBone::ThumbTip => hand_resource.right.thumb.tip = boneid,
-                    HandBone::IndexMetacarpal => hand_resource.right.index.metacarpal = boneid,
-                    HandBone::IndexProximal => hand_resource.right.index.proximal = boneid,
-                    HandBone::IndexIntermediate => hand_resource.right.index.intermediate = boneid,
-                    HandBone::IndexDistal => hand_resource.right.index.distal = boneid,
-                    HandBone::IndexTip => hand_resource.right.index.tip = boneid,
-                    HandBone::MiddleMetacarpal => hand_resource.right.middle.metacarpal = boneid,
-                    HandBone::MiddleProximal => hand_resource.right.middle.proximal = boneid,
-                    HandBone::MiddleIntermediate => {
-                        hand_resource.right.middle.intermediate = boneid
-                    }
-                    HandBone::MiddleDistal => hand_resource.right.middle.distal = boneid,
-                    HandBone::MiddleTip => hand_resource.right.middle.tip = boneid,
-                    HandBone::RingMetacarpal => hand_resource.right.ring.metacarpal = boneid,
-                    HandBone::RingProximal => hand_resource.right.ring.proximal = boneid,
-                    HandBone::RingIntermediate => hand_resource.right.ring.intermediate = boneid,
-                    HandBone::RingDistal => hand_resource.right.ring.distal = boneid,
-                    HandBone::RingTip => hand_resource.right.ring.tip = boneid,
-                    HandBone::LittleMetacarpal => hand_resource.right.little.metacarpal = boneid,
-                    HandBone::LittleProximal => hand_resource.right.little.proximal = boneid,
-                    HandBone::LittleIntermediate => {
-                        hand_resource.right.little.intermediate = boneid
-                    }
-                    HandBone::LittleDistal => hand_resource.right.little.distal = boneid,
-                    HandBone::LittleTip => hand_resource.right.little.tip = boneid,
-                },
-            }
+    pub fn entity(&self, hand: Hand, bone: HandBone) -> Option<Entity> {
+        self.entities(hand).map(|joints| joints.get(bone))
+    }
+
+    /// the world transform of a joint
+    pub fn get(&self, hand: Hand, bone: HandBone) -> Option<&GlobalTransform> {
+        self.joints.get(self.entity(hand, bone)?).ok()
+    }
+
+    /// the world transforms of all joints of a hand, in the order of [`HandBone::get_all_bones`]
+    pub fn transforms(&self, hand: Hand) -> Option<[Transform; 26]> {
+        let joints = self.entities(hand)?;
+        let mut transforms = [Transform::IDENTITY; 26];
+        for (transform, entity) in transforms.iter_mut().zip(joints.0.iter()) {
+            *transform = self.joints.get(*entity).ok()?.compute_transform();
         }
+        Some(transforms)
     }
-    commands.insert_resource(hand_resource);
 }
 
-#[derive(Component, Debug, Clone, Copy)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum HandBone {
     Palm,
     Wrist,
@@ -324,6 +290,37 @@ impl HandBone {
             HandBone::LittleTip,
         ]
     }
+    /// the joint this one is attached to, `None` for the wrist
+    pub fn get_parent(&self) -> Option<HandBone> {
+        match self {
+            HandBone::Wrist => None,
+            HandBone::Palm
+            | HandBone::ThumbMetacarpal
+            | HandBone::IndexMetacarpal
+            | HandBone::MiddleMetacarpal
+            | HandBone::RingMetacarpal
+            | HandBone::LittleMetacarpal => Some(HandBone::Wrist),
+            HandBone::ThumbProximal => Some(HandBone::ThumbMetacarpal),
+            HandBone::ThumbDistal => Some(HandBone::ThumbProximal),
+            HandBone::ThumbTip => Some(HandBone::ThumbDistal),
+            HandBone::IndexProximal => Some(HandBone::IndexMetacarpal),
+            HandBone::IndexIntermediate => Some(HandBone::IndexProximal),
+            HandBone::IndexDistal => Some(HandBone::IndexIntermediate),
+            HandBone::IndexTip => Some(HandBone::IndexDistal),
+            HandBone::MiddleProximal => Some(HandBone::MiddleMetacarpal),
+            HandBone::MiddleIntermediate => Some(HandBone::MiddleProximal),
+            HandBone::MiddleDistal => Some(HandBone::MiddleIntermediate),
+            HandBone::MiddleTip => Some(HandBone::MiddleDistal),
+            HandBone::RingProximal => Some(HandBone::RingMetacarpal),
+            HandBone::RingIntermediate => Some(HandBone::RingProximal),
+            HandBone::RingDistal => Some(HandBone::RingIntermediate),
+            HandBone::RingTip => Some(HandBone::RingDistal),
+            HandBone::LittleProximal => Some(HandBone::LittleMetacarpal),
+            HandBone::LittleIntermediate => Some(HandBone::LittleProximal),
+            HandBone::LittleDistal => Some(HandBone::LittleIntermediate),
+            HandBone::LittleTip => Some(HandBone::LittleDistal),
+        }
+    }
     pub fn get_index_from_bone(&self) -> usize {
         match &self {
             HandBone::Palm => 0,
@@ -356,229 +353,12 @@ impl HandBone {
     }
 }
 
-pub fn update_hand_states(
-    oculus_controller: Res<OculusController>,
-    hand_states_option: Option<ResMut<HandStatesResource>>,
-    frame_state: Res<XrFrameState>,
-    xr_input: Res<XrInput>,
-    instance: Res<XrInstance>,
-    session: Res<XrSession>,
-) {
-    match hand_states_option {
-        Some(mut hands) => {
-            //lock frame
-            let frame_state = *frame_state.lock().unwrap();
-            //get controller
-            let controller =
-                oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
-
-            //right hand
-            let squeeze = controller.squeeze(Hand::Right);
-            let trigger_state = controller.trigger(Hand::Right);
-            let calc_trigger_state = match controller.trigger_touched(Hand::Right) {
-                true => match trigger_state > 0.0 {
-                    true => TriggerState::PULLED,
-                    false => TriggerState::TOUCHED,
-                },
-                false => TriggerState::OFF,
-            };
-            //button a
-            let mut a_state = ButtonState::OFF;
-            if controller.a_button_touched() {
-                a_state = ButtonState::TOUCHED;
-            }
-            if controller.a_button() {
-                a_state = ButtonState::PRESSED;
-            }
-
-            //button b
-            let mut b_state = ButtonState::OFF;
-            if controller.b_button_touched() {
-                b_state = ButtonState::TOUCHED;
-            }
-            if controller.b_button() {
-                b_state = ButtonState::PRESSED;
-            }
-
-            let thumbstick_state = controller.thumbstick(Hand::Right);
-            let calc_thumbstick_state = match controller.thumbstick_touch(Hand::Right) {
-                true => match thumbstick_state.x > 0.0 || thumbstick_state.y > 0.0 {
-                    true => ThumbstickState::PRESSED,
-                    false => ThumbstickState::TOUCHED,
-                },
-                false => ThumbstickState::OFF,
-            };
-
-            let right_state = HandState {
-                grip: squeeze,
-                trigger_state: calc_trigger_state,
-                a_button: a_state,
-                b_button: b_state,
-                thumbstick: calc_thumbstick_state,
-            };
-
-            //left
-            let squeeze = controller.squeeze(Hand::Left);
-            let trigger_state = controller.trigger(Hand::Left);
-            let calc_trigger_state = match controller.trigger_touched(Hand::Left) {
-                true => match trigger_state > 0.0 {
-                    true => TriggerState::PULLED,
-                    false => TriggerState::TOUCHED,
-                },
-                false => TriggerState::OFF,
-            };
-            //button a
-            let mut a_state = ButtonState::OFF;
-            if controller.x_button_touched() {
-                a_state = ButtonState::TOUCHED;
-            }
-            if controller.x_button() {
-                a_state = ButtonState::PRESSED;
-            }
-
-            //button b
-            let mut b_state = ButtonState::OFF;
-            if controller.y_button_touched() {
-                b_state = ButtonState::TOUCHED;
-            }
-            if controller.y_button() {
-                b_state = ButtonState::PRESSED;
-            }
-
-            let thumbstick_state = controller.thumbstick(Hand::Left);
-            let calc_thumbstick_state = match controller.thumbstick_touch(Hand::Left) {
-                true => match thumbstick_state.x > 0.0 || thumbstick_state.y > 0.0 {
-                    true => ThumbstickState::PRESSED,
-                    false => ThumbstickState::TOUCHED,
-                },
-                false => ThumbstickState::OFF,
-            };
-
-            let left_state = HandState {
-                grip: squeeze,
-                trigger_state: calc_trigger_state,
-                a_button: a_state,
-                b_button: b_state,
-                thumbstick: calc_thumbstick_state,
-            };
-
-            hands.left = left_state;
-            hands.right = right_state;
-        }
-        None => info!("hand states resource not init yet"),
-    }
-}
-
-#[derive(Clone, Copy)]
-pub enum ButtonState {
-    OFF,
-    TOUCHED,
-    PRESSED,
-}
-
-impl Default for ButtonState {
-    fn default() -> Self {
-        ButtonState::OFF
-    }
-}
-#[derive(Clone, Copy)]
-pub enum ThumbstickState {
-    OFF,
-    TOUCHED,
-    PRESSED,
-}
-
-impl Default for ThumbstickState {
-    fn default() -> Self {
-        ThumbstickState::OFF
-    }
-}
-#[derive(Clone, Copy)]
-pub enum TriggerState {
-    OFF,
-    TOUCHED,
-    PULLED,
-}
-
-impl Default for TriggerState {
-    fn default() -> Self {
-        TriggerState::OFF
-    }
-}
-
-#[derive(Default, Resource)]
-pub struct HandStatesResource {
-    pub left: HandState,
-    pub right: HandState,
-}
-
-#[derive(Clone, Copy)]
-pub struct HandState {
-    pub grip: f32,
-    pub trigger_state: TriggerState,
-    pub a_button: ButtonState,
-    pub b_button: ButtonState,
-    pub thumbstick: ThumbstickState,
-}
-
-impl HandState {
-    pub fn get_index_curl(&self) -> f32 {
-        match self.trigger_state {
-            TriggerState::OFF => 0.0,
-            TriggerState::TOUCHED => 0.50,
-            TriggerState::PULLED => 1.0,
-        }
-    }
-
-    pub fn get_thumb_curl(&self) -> f32 {
-        match self.thumbstick {
-            ThumbstickState::OFF => (),
-            ThumbstickState::TOUCHED => return 0.25,
-            ThumbstickState::PRESSED => return 0.25,
-        };
-
-        match self.a_button {
-            ButtonState::OFF => (),
-            ButtonState::TOUCHED => return 0.25,
-            ButtonState::PRESSED => return 0.25,
-        };
-
-        match self.b_button {
-            ButtonState::OFF => (),
-            ButtonState::TOUCHED => return 0.25,
-            ButtonState::PRESSED => return 0.25,
-        };
-        //if no thumb actions taken return open position
-        return 0.0;
-    }
-}
-
-impl Default for HandState {
-    fn default() -> Self {
-        Self {
-            grip: Default::default(),
-            trigger_state: Default::default(),
-            a_button: Default::default(),
-            b_button: Default::default(),
-            thumbstick: Default::default(),
-        }
-    }
-}
-
-pub fn update_hand_bones_emulated(
+/// The procedural hand, used when [`EmulatedHandConfig`] has no open and closed pose
+pub fn emulated_hand_transforms(
     controller_transform: Transform,
     hand: Hand,
-    hand_state: HandState,
-
-    hand_bone_query: &mut Query<(
-        Entity,
-        &mut Transform,
-        &HandBone,
-        &Hand,
-        Option<&mut HandBoneRadius>,
-        Without<OpenXRTrackingRoot>,
-    )>,
-) {
+    curls: &EmulatedFingerCurls,
+) -> [Transform; 26] {
     let left_hand_rot = Quat::from_rotation_y(180.0 * PI / 180.0);
     let hand_translation: Vec3 = match hand {
         Hand::Left => controller_transform.translation,
@@ -598,9 +378,8 @@ pub fn update_hand_bones_emulated(
     let mut calc_transforms = [Transform::default(); 26];
 
     //curl represents how closed the hand is from 0 to 1;
-    let grip_curl = hand_state.grip;
-    let index_curl = hand_state.get_index_curl();
-    let thumb_curl = hand_state.get_thumb_curl();
+    let thumb_curl = curls.get(Finger::Thumb);
+    let index_curl = curls.get(Finger::Index);
     //get palm quat
     let y = Quat::from_rotation_y(-90.0 * PI / 180.0);
     let x = Quat::from_rotation_x(-90.0 * PI / 180.0);
@@ -734,7 +513,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curls.get(Finger::Middle));
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -782,7 +561,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curls.get(Finger::Ring));
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -831,7 +610,7 @@ pub fn update_hand_bones_emulated(
     for bone in thumb_joints.iter() {
         match prior_start {
             Some(start) => {
-                let curl_angle: f32 = get_bone_curl_angle(*bone, grip_curl);
+                let curl_angle: f32 = get_bone_curl_angle(*bone, curls.get(Finger::Little));
                 let tp_lrot = Quat::from_rotation_x(curl_angle * PI / 180.0);
                 let tp_quat = prior_quat.unwrap().mul_quat(tp_lrot);
                 let thumb_prox = hand_transform_array[*bone];
@@ -864,45 +643,7 @@ pub fn update_hand_bones_emulated(
         }
     }
 
-    //now that we have all the transforms lets assign them
-    for (_, mut transform, handbone, bonehand, _, _) in hand_bone_query.iter_mut() {
-        if *bonehand == hand {
-            //if the hands match lets go
-            let index = match_index(handbone);
-            *transform = calc_transforms[index];
-        }
-    }
-}
-
-fn match_index(handbone: &HandBone) -> HandJoint {
-    match handbone {
-        HandBone::Palm => HandJoint::PALM,
-        HandBone::Wrist => HandJoint::WRIST,
-        HandBone::ThumbMetacarpal => HandJoint::THUMB_METACARPAL,
-        HandBone::ThumbProximal => HandJoint::THUMB_PROXIMAL,
-        HandBone::ThumbDistal => HandJoint::THUMB_DISTAL,
-        HandBone::ThumbTip => HandJoint::THUMB_TIP,
-        HandBone::IndexMetacarpal => HandJoint::INDEX_METACARPAL,
-        HandBone::IndexProximal => HandJoint::INDEX_PROXIMAL,
-        HandBone::IndexIntermediate => HandJoint::INDEX_INTERMEDIATE,
-        HandBone::IndexDistal => HandJoint::INDEX_DISTAL,
-        HandBone::IndexTip => HandJoint::INDEX_TIP,
-        HandBone::MiddleMetacarpal => HandJoint::MIDDLE_METACARPAL,
-        HandBone::MiddleProximal => HandJoint::MIDDLE_PROXIMAL,
-        HandBone::MiddleIntermediate => HandJoint::MIDDLE_INTERMEDIATE,
-        HandBone::MiddleDistal => HandJoint::MIDDLE_DISTAL,
-        HandBone::MiddleTip => HandJoint::MIDDLE_TIP,
-        HandBone::RingMetacarpal => HandJoint::RING_METACARPAL,
-        HandBone::RingProximal => HandJoint::RING_PROXIMAL,
-        HandBone::RingIntermediate => HandJoint::RING_INTERMEDIATE,
-        HandBone::RingDistal => HandJoint::RING_DISTAL,
-        HandBone::RingTip => HandJoint::RING_TIP,
-        HandBone::LittleMetacarpal => HandJoint::LITTLE_METACARPAL,
-        HandBone::LittleProximal => HandJoint::LITTLE_PROXIMAL,
-        HandBone::LittleIntermediate => HandJoint::LITTLE_INTERMEDIATE,
-        HandBone::LittleDistal => HandJoint::LITTLE_DISTAL,
-        HandBone::LittleTip => HandJoint::LITTLE_TIP,
-    }
+    calc_transforms
 }
 
 fn get_bone_curl_angle(bone: HandJoint, curl: f32) -> f32 {
@@ -1053,90 +794,281 @@ fn log_hand(hand_pose: [Posef; 26]) {
     );
 }
 
+/// Picks the [`HandInputSource`] of every hand from what the runtime reports for it
+pub fn update_hand_input_sources(
+    mode: Res<HandInputSourceMode>,
+    mut hands: Query<
+        (Entity, &Hand, &mut HandInputSource, &mut HandInputActivity),
+        With<OpenXRHand>,
+    >,
+    hand_tracking: Option<Res<HandTrackingTracker>>,
+    oculus_controller: Res<OculusController>,
+    mut profile_changed: EventReader<XrInteractionProfileChanged>,
+    mut source_changed: EventWriter<HandInputSourceChanged>,
+    mut profiles_queried: Local<bool>,
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    xr_input: Res<XrInput>,
+    frame_state: Res<XrFrameState>,
+) {
+    //the runtime sends an event once profiles are bound, but we might have missed it
+    let update_profiles = profile_changed.read().count() > 0 || !*profiles_queried;
+    *profiles_queried = true;
+    let hand_ref = hand_tracking
+        .as_ref()
+        .map(|tracking| tracking.get_ref(&xr_input, &frame_state));
+    for (entity, hand, mut source, mut activity) in hands.iter_mut() {
+        if update_profiles {
+            activity.interaction_profile = get_interaction_profile(&instance, &session, *hand);
+        }
+        activity.joints_active = match &hand_ref {
+            Some(hand_ref) => matches!(hand_ref.get_poses(*hand), Ok(Some(_))),
+            None => false,
+        };
+        let grip_space = match hand {
+            Hand::Left => &oculus_controller.grip_space.left,
+            Hand::Right => &oculus_controller.grip_space.right,
+        };
+        activity.controller_tracked =
+            match grip_space.locate(&xr_input.stage, frame_state.predicted_display_time) {
+                Ok(location) => location.location_flags.contains(
+                    xr::SpaceLocationFlags::POSITION_TRACKED
+                        | xr::SpaceLocationFlags::ORIENTATION_TRACKED,
+                ),
+                Err(_) => false,
+            };
+        let new_source = match *mode {
+            HandInputSourceMode::Fixed(source) => source,
+            HandInputSourceMode::Automatic => {
+                if activity.joints_active {
+                    HandInputSource::OpenXr
+                } else if activity.controller_tracked && activity.interaction_profile.is_some() {
+                    HandInputSource::Emulated
+                } else {
+                    //nothing tracked right now, keep what we had to avoid flickering
+                    *source
+                }
+            }
+        };
+        if new_source != *source {
+            info!("{:?} hand input source changed to {:?}", hand, new_source);
+            source_changed.send(HandInputSourceChanged {
+                entity,
+                hand: *hand,
+                previous: *source,
+                source: new_source,
+            });
+            *source = new_source;
+        }
+    }
+}
+
+fn get_interaction_profile(
+    instance: &xr::Instance,
+    session: &xr::Session<xr::AnyGraphics>,
+    hand: Hand,
+) -> Option<String> {
+    let user_path = match hand {
+        Hand::Left => "/user/hand/left",
+        Hand::Right => "/user/hand/right",
+    };
+    let profile = instance
+        .string_to_path(user_path)
+        .and_then(|path| session.current_interaction_profile(path))
+        .ok()?;
+    if profile == xr::Path::NULL {
+        return None;
+    }
+    instance.path_to_string(profile).ok()
+}
+
 pub fn update_hand_skeletons(
-    tracking_root_query: Query<(&Transform, With<OpenXRTrackingRoot>)>,
-    right_controller_query: Query<(&GlobalTransform, With<OpenXRRightController>)>,
-    left_controller_query: Query<(&GlobalTransform, With<OpenXRLeftController>)>,
-    hand_states_option: Option<ResMut<HandStatesResource>>,
+    right_controller_query: Query<&Transform, (With<OpenXRRightController>, Without<HandBone>)>,
+    left_controller_query: Query<&Transform, (With<OpenXRLeftController>, Without<HandBone>)>,
+    emulated_config: Res<EmulatedHandConfig>,
+    hand_poses: Option<Res<Assets<HandPose>>>,
     mut commands: Commands,
-    mut hand_bone_query: Query<(
-        Entity,
-        &mut Transform,
-        &HandBone,
-        &Hand,
-        Option<&mut HandBoneRadius>,
-        Without<OpenXRTrackingRoot>,
-    )>,
-    input_source: Option<Res<HandInputSource>>,
-    hand_tracking: Res<HandTrackingTracker>,
+    mut hands: Query<
+        (
+            Entity,
+            &Hand,
+            &HandInputSource,
+            &EmulatedFingerCurls,
+            &HandJointEntities,
+            &GlobalTransform,
+            Option<&mut HandTrackingAim>,
+        ),
+        With<OpenXRHand>,
+    >,
+    mut joint_query: Query<
+        (
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut HandBoneVelocity,
+            Option<&mut HandBoneRadius>,
+        ),
+        (With<HandBone>, Without<OpenXRHand>),
+    >,
+    hand_tracking: Option<Res<HandTrackingTracker>>,
+    motion_range: Option<Res<HandJointsMotionRange>>,
     xr_input: Res<XrInput>,
     xr_frame_state: Res<XrFrameState>,
+    mut error_events: EventWriter<XrErrorEvent>,
 ) {
-    match input_source {
-        Some(res) => match *res {
-            HandInputSource::Emulated => {
-                // info!("hand input source is emulated");
-                match hand_states_option {
-                    Some(hands) => {
-                        let left_hand_transform = left_controller_query
-                            .get_single()
-                            .unwrap()
-                            .0
-                            .compute_transform();
-                        update_hand_bones_emulated(
-                            left_hand_transform,
-                            Hand::Left,
-                            hands.left,
-                            &mut hand_bone_query,
-                        );
-                        let right_hand_transform = right_controller_query
-                            .get_single()
-                            .unwrap()
-                            .0
-                            .compute_transform();
-                        update_hand_bones_emulated(
-                            right_hand_transform,
-                            Hand::Right,
-                            hands.right,
-                            &mut hand_bone_query,
-                        );
+    for (hand_entity, hand, source, curls, joints, root, tracking_aim) in hands.iter_mut() {
+        //without hand tracking support the hands are always emulated
+        let hand_tracking = match source {
+            HandInputSource::OpenXr => hand_tracking.as_ref(),
+            HandInputSource::Emulated => None,
+        };
+        //joint poses in the tracking space
+        let (poses, data) = match hand_tracking {
+            None => {
+                let controller_transform = match hand {
+                    Hand::Left => left_controller_query.get_single(),
+                    Hand::Right => right_controller_query.get_single(),
+                };
+                let controller_transform = match controller_transform {
+                    Ok(transform) => *transform,
+                    Err(_) => continue,
+                };
+                let poses = match (&emulated_config.open_pose, &emulated_config.closed_pose) {
+                    (Some(open), Some(closed)) => hand_poses
+                        .as_ref()
+                        .and_then(|poses| Some((poses.get(open)?, poses.get(closed)?))),
+                    _ => None,
+                };
+                let joints = match poses {
+                    Some((open, closed)) => {
+                        let offset = emulated_config.wrist_offset;
+                        blend_hand_poses(
+                            &pose_in_grip_space(open, *hand, offset),
+                            &pose_in_grip_space(closed, *hand, offset),
+                            curls,
+                        )
+                        .map(|joint| controller_transform.mul_transform(joint))
                     }
-                    None => info!("hand states resource not initialized yet"),
-                }
+                    None => emulated_hand_transforms(controller_transform, *hand, curls),
+                };
+                (joints, None)
             }
-            HandInputSource::OpenXr => {
+            Some(hand_tracking) => {
                 let hand_ref = hand_tracking.get_ref(&xr_input, &xr_frame_state);
-                let (root_transform, _) = tracking_root_query.get_single().unwrap();
-                let left_data = hand_ref.get_left_poses();
-                let right_data = hand_ref.get_right_poses();
+                let motion_range = motion_range.as_deref().copied().unwrap_or_default();
+                let data = match hand_ref.locate_joints(*hand, motion_range) {
+                    Ok(Some(data)) => data,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        error_events.send(XrErrorEvent(err));
+                        continue;
+                    }
+                };
+                (
+                    data.locations.map(|joint| pose_to_transform(joint.pose)),
+                    Some(data),
+                )
+            }
+        };
+
+        let aim = data
+            .as_ref()
+            .and_then(|data| data.aim.as_ref())
+            .and_then(HandTrackingAim::from_raw);
+        match (tracking_aim, aim) {
+            (Some(mut tracking_aim), Some(aim)) => *tracking_aim = aim,
+            (None, Some(aim)) => {
+                commands.entity(hand_entity).insert(aim);
+            }
+            (Some(_), None) => {
+                commands.entity(hand_entity).remove::<HandTrackingAim>();
+            }
+            (None, None) => (),
+        }
 
-                for (entity, mut transform, bone, hand, radius, _) in hand_bone_query.iter_mut() {
-                    let bone_data = match (hand, left_data, right_data) {
-                        (Hand::Left, Some(data), _) => data[bone.get_index_from_bone()],
-                        (Hand::Right, _, Some(data)) => data[bone.get_index_from_bone()],
-                        _ => continue,
-                    };
+        let root_rotation = root.compute_transform().rotation;
+        let transforms = joint_transforms(root, &poses);
+        for bone in HandBone::get_all_bones() {
+            let index = bone.get_index_from_bone();
+            let entity = joints.get(bone);
+            let (mut transform, mut global_transform, mut velocity, radius) =
+                match joint_query.get_mut(entity) {
+                    Ok(joint) => joint,
+                    Err(_) => continue,
+                };
+            (*transform, *global_transform) = transforms[index];
+            match &data {
+                Some(data) => {
+                    *velocity = HandBoneVelocity::from_raw(&data.velocities[index], root_rotation);
+                    let joint_radius = data.locations[index].radius;
                     match radius {
-                        Some(mut r) => r.0 = bone_data.radius,
+                        Some(mut r) => r.0 = joint_radius,
                         None => {
-                            commands
-                                .entity(entity)
-                                .insert(HandBoneRadius(bone_data.radius));
+                            commands.entity(entity).insert(HandBoneRadius(joint_radius));
                         }
                     }
-                    *transform = transform
-                        .with_translation(
-                            root_transform.transform_point(bone_data.pose.position.to_vec3()),
-                        )
-                        .with_rotation(
-                            root_transform.rotation * bone_data.pose.orientation.to_quat(),
-                        )
                 }
+                //the runtime reports no joint velocities for emulated hands
+                None => *velocity = HandBoneVelocity::default(),
+            }
+        }
+    }
+}
+
+/// Turns the poses of all joints relative to the hand root into local transforms relative to
+/// the parent joints.
+///
+/// The global transforms are returned as well so systems running before the next transform
+/// propagation already see the new poses.
+pub fn joint_transforms(
+    root: &GlobalTransform,
+    poses: &[Transform; 26],
+) -> [(Transform, GlobalTransform); 26] {
+    HandBone::get_all_bones().map(|bone| {
+        let pose = poses[bone.get_index_from_bone()];
+        let local = match bone.get_parent() {
+            Some(parent) => {
+                let parent = poses[parent.get_index_from_bone()];
+                let inverse_rotation = parent.rotation.inverse();
+                Transform {
+                    translation: inverse_rotation * (pose.translation - parent.translation),
+                    rotation: inverse_rotation * pose.rotation,
+                    ..default()
+                }
+            }
+            None => pose,
+        };
+        (local, root.mul_transform(pose))
+    })
+}
+
+pub fn update_hand_rigs(
+    sources: Query<(&Hand, &HandJointEntities), With<OpenXRHand>>,
+    rigs: Query<(&HandRig, &HandJointEntities, &GlobalTransform), Without<OpenXRHand>>,
+    mut joint_query: Query<
+        (&mut Transform, &mut GlobalTransform),
+        (With<HandBone>, Without<OpenXRHand>, Without<HandRig>),
+    >,
+) {
+    for (rig, rig_joints, rig_root) in rigs.iter() {
+        let source = match sources.iter().find(|(hand, _)| **hand == rig.source) {
+            Some((_, joints)) => joints,
+            None => continue,
+        };
+        let to_rig = rig_root.affine().inverse();
+        let mut poses = [Transform::IDENTITY; 26];
+        for (pose, entity) in poses.iter_mut().zip(source.0.iter()) {
+            if let Ok((_, global_transform)) = joint_query.get(*entity) {
+                *pose = Transform::from_matrix((to_rig * global_transform.affine()).into());
+                if rig.mirror {
+                    *pose = mirror_transform(*pose);
+                }
+            }
+        }
+        let transforms = joint_transforms(rig_root, &poses);
+        for (entity, joint) in rig_joints.0.iter().zip(transforms) {
+            if let Ok((mut transform, mut global_transform)) = joint_query.get_mut(*entity) {
+                (*transform, *global_transform) = joint;
             }
-        },
-        None => {
-            info!("hand input source not initialized");
-            return;
         }
     }
 }
@@ -1144,12 +1076,77 @@ pub fn update_hand_skeletons(
 #[derive(Debug, Component, DerefMut, Deref)]
 pub struct HandBoneRadius(pub f32);
 
+/// The aim of a tracked hand as computed by the runtime with `XR_FB_hand_tracking_aim`.
+///
+/// Only present on an [`OpenXRHand`] while the runtime reports a valid aim.
+#[derive(Debug, Component, Clone, Copy)]
+pub struct HandTrackingAim {
+    /// the pointing ray with forward along -z, in the tracking space like the controller
+    /// [`AimPose`](super::trackers::AimPose)
+    pub pose: Transform,
+    /// from 0 to 1
+    pub pinch_strength_index: f32,
+    pub index_pinching: bool,
+    /// the user is doing the system gesture, the hand shouldn't interact with anything
+    pub system_gesture: bool,
+}
+
+impl HandTrackingAim {
+    fn from_raw(aim: &xr::sys::HandTrackingAimStateFB) -> Option<Self> {
+        if !aim.status.contains(xr::sys::HandTrackingAimFlagsFB::VALID) {
+            return None;
+        }
+        Some(Self {
+            pose: pose_to_transform(aim.aim_pose),
+            pinch_strength_index: aim.pinch_strength_index,
+            index_pinching: aim
+                .status
+                .contains(xr::sys::HandTrackingAimFlagsFB::INDEX_PINCHING),
+            system_gesture: aim
+                .status
+                .contains(xr::sys::HandTrackingAimFlagsFB::SYSTEM_GESTURE),
+        })
+    }
+}
+
+/// Velocity of a hand joint in world space, as reported by the runtime
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct HandBoneVelocity {
+    /// meters per second
+    pub linear: Vec3,
+    /// axis scaled by radians per second
+    pub angular: Vec3,
+    pub linear_valid: bool,
+    pub angular_valid: bool,
+}
+
+impl HandBoneVelocity {
+    fn from_raw(velocity: &xr::HandJointVelocityEXT, root_rotation: Quat) -> Self {
+        let flags = velocity.velocity_flags;
+        let linear_valid = flags.contains(xr::SpaceVelocityFlags::LINEAR_VALID);
+        let angular_valid = flags.contains(xr::SpaceVelocityFlags::ANGULAR_VALID);
+        Self {
+            linear: match linear_valid {
+                true => root_rotation * velocity.linear_velocity.to_vec3(),
+                false => Vec3::ZERO,
+            },
+            angular: match angular_valid {
+                true => root_rotation * velocity.angular_velocity.to_vec3(),
+                false => Vec3::ZERO,
+            },
+            linear_valid,
+            angular_valid,
+        }
+    }
+}
+
 pub fn draw_hand_entities(
     mut gizmos: Gizmos,
-    query: Query<(&Transform, &HandBone, Option<&HandBoneRadius>)>,
+    query: Query<(&GlobalTransform, &HandBone, Option<&HandBoneRadius>)>,
 ) {
     for (transform, hand_bone, hand_bone_radius) in query.iter() {
         let (radius, color) = get_bone_gizmo_style(hand_bone);
+        let transform = transform.compute_transform();
         gizmos.sphere(
             transform.translation,
             transform.rotation,
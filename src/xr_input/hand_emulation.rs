@@ -0,0 +1,379 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use openxr as xr;
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::{
+    actions::XrSuggestedBindings,
+    hand::{HandBone, OpenXRHand},
+    hand_pose_matching::HandPose,
+    oculus_touch::{
+        init_subaction_path, subaction_path, ActionSets, OculusController, OCULUS_TOUCH_PROFILE,
+    },
+    Hand,
+};
+
+pub const VALVE_INDEX_PROFILE: &str = "/interaction_profiles/valve/index_controller";
+pub const HTC_VIVE_PROFILE: &str = "/interaction_profiles/htc/vive_controller";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Finger {
+    Thumb,
+    Index,
+    Middle,
+    Ring,
+    Little,
+}
+
+impl Finger {
+    pub const fn all() -> [Finger; 5] {
+        [
+            Finger::Thumb,
+            Finger::Index,
+            Finger::Middle,
+            Finger::Ring,
+            Finger::Little,
+        ]
+    }
+
+    /// the joints of the finger from the metacarpal to the tip
+    pub fn bones(&self) -> &'static [HandBone] {
+        match self {
+            Finger::Thumb => &[
+                HandBone::ThumbMetacarpal,
+                HandBone::ThumbProximal,
+                HandBone::ThumbDistal,
+                HandBone::ThumbTip,
+            ],
+            Finger::Index => &[
+                HandBone::IndexMetacarpal,
+                HandBone::IndexProximal,
+                HandBone::IndexIntermediate,
+                HandBone::IndexDistal,
+                HandBone::IndexTip,
+            ],
+            Finger::Middle => &[
+                HandBone::MiddleMetacarpal,
+                HandBone::MiddleProximal,
+                HandBone::MiddleIntermediate,
+                HandBone::MiddleDistal,
+                HandBone::MiddleTip,
+            ],
+            Finger::Ring => &[
+                HandBone::RingMetacarpal,
+                HandBone::RingProximal,
+                HandBone::RingIntermediate,
+                HandBone::RingDistal,
+                HandBone::RingTip,
+            ],
+            Finger::Little => &[
+                HandBone::LittleMetacarpal,
+                HandBone::LittleProximal,
+                HandBone::LittleIntermediate,
+                HandBone::LittleDistal,
+                HandBone::LittleTip,
+            ],
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Finger::Thumb => 0,
+            Finger::Index => 1,
+            Finger::Middle => 2,
+            Finger::Ring => 3,
+            Finger::Little => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmulatedCurlInputKind {
+    /// an analog value from 0 to 1, e.g. `input/trigger/value`
+    Value,
+    /// a touch sensor or button, e.g. `input/thumbrest/touch`
+    Touch,
+}
+
+/// One action that curls fingers of an emulated hand
+#[derive(Clone, Debug)]
+pub struct EmulatedCurlInput {
+    /// name of the created action, has to be unique and a valid OpenXR action name
+    pub name: String,
+    pub kind: EmulatedCurlInputKind,
+    pub fingers: Vec<Finger>,
+    /// curl of the fingers while the input is fully pressed
+    pub curl: f32,
+    /// interaction profile and input path, relative to the hand unless it starts with `/user/`
+    pub bindings: Vec<(String, String)>,
+}
+
+impl EmulatedCurlInput {
+    pub fn new(
+        name: impl Into<String>,
+        kind: EmulatedCurlInputKind,
+        fingers: &[Finger],
+        curl: f32,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            fingers: fingers.to_vec(),
+            curl,
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn with_binding(mut self, profile: &str, path: &str) -> Self {
+        self.bindings.push((profile.to_string(), path.to_string()));
+        self
+    }
+}
+
+/// How emulated hands are posed from the controller inputs.
+///
+/// Every finger curls as far as the strongest input driving it and is blended between the
+/// open and closed pose. Without both pose assets the built in procedural hand is used, loading
+/// them needs the [`HandPoseMatchingPlugin`](super::hand_pose_matching::HandPoseMatchingPlugin).
+#[derive(Resource, Clone, Debug)]
+pub struct EmulatedHandConfig {
+    pub inputs: Vec<EmulatedCurlInput>,
+    /// profiles besides Oculus Touch the controller grip and aim poses get bound for
+    pub pose_profiles: Vec<String>,
+    pub open_pose: Option<Handle<HandPose>>,
+    pub closed_pose: Option<Handle<HandPose>>,
+    /// the wrist relative to the grip pose of the right controller, mirrored for the left one
+    pub wrist_offset: Transform,
+    /// how fast the fingers follow the inputs in curl per second, 0 follows instantly
+    pub curl_speed: f32,
+}
+
+impl Default for EmulatedHandConfig {
+    fn default() -> Self {
+        use EmulatedCurlInputKind::*;
+        let inputs = vec![
+            EmulatedCurlInput::new("emulated_index_value", Value, &[Finger::Index], 1.0)
+                .with_binding(OCULUS_TOUCH_PROFILE, "input/trigger/value")
+                .with_binding(VALVE_INDEX_PROFILE, "input/trigger/value")
+                .with_binding(HTC_VIVE_PROFILE, "input/trigger/value"),
+            EmulatedCurlInput::new("emulated_index_touch", Touch, &[Finger::Index], 0.5)
+                .with_binding(OCULUS_TOUCH_PROFILE, "input/trigger/touch")
+                .with_binding(VALVE_INDEX_PROFILE, "input/trigger/touch"),
+            EmulatedCurlInput::new(
+                "emulated_grip_value",
+                Value,
+                &[Finger::Middle, Finger::Ring, Finger::Little],
+                1.0,
+            )
+            .with_binding(OCULUS_TOUCH_PROFILE, "input/squeeze/value")
+            .with_binding(VALVE_INDEX_PROFILE, "input/squeeze/value")
+            .with_binding(HTC_VIVE_PROFILE, "input/squeeze/click"),
+            EmulatedCurlInput::new("emulated_thumb_touch", Touch, &[Finger::Thumb], 0.25)
+                .with_binding(OCULUS_TOUCH_PROFILE, "input/thumbstick/touch")
+                .with_binding(OCULUS_TOUCH_PROFILE, "input/thumbrest/touch")
+                .with_binding(OCULUS_TOUCH_PROFILE, "/user/hand/left/input/x/touch")
+                .with_binding(OCULUS_TOUCH_PROFILE, "/user/hand/left/input/y/touch")
+                .with_binding(OCULUS_TOUCH_PROFILE, "/user/hand/right/input/a/touch")
+                .with_binding(OCULUS_TOUCH_PROFILE, "/user/hand/right/input/b/touch")
+                .with_binding(VALVE_INDEX_PROFILE, "input/thumbstick/touch")
+                .with_binding(VALVE_INDEX_PROFILE, "input/trackpad/touch")
+                .with_binding(VALVE_INDEX_PROFILE, "input/a/touch")
+                .with_binding(VALVE_INDEX_PROFILE, "input/b/touch")
+                .with_binding(HTC_VIVE_PROFILE, "input/trackpad/touch"),
+        ];
+        Self {
+            inputs,
+            pose_profiles: vec![VALVE_INDEX_PROFILE.into(), HTC_VIVE_PROFILE.into()],
+            open_pose: None,
+            closed_pose: None,
+            //the palm of the right hand faces -X of the grip, the wrist sits behind it
+            wrist_offset: Transform::from_xyz(0.0, 0.0, 0.1)
+                .with_rotation(Quat::from_rotation_z(-FRAC_PI_2)),
+            curl_speed: 8.0,
+        }
+    }
+}
+
+/// Current curl of the fingers of an emulated hand, from 0 (open) to 1 (closed)
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct EmulatedFingerCurls(pub [f32; 5]);
+
+impl EmulatedFingerCurls {
+    pub fn get(&self, finger: Finger) -> f32 {
+        self.0[finger.index()]
+    }
+}
+
+enum EmulatedCurlAction {
+    Value(xr::Action<f32>),
+    Touch(xr::Action<bool>),
+}
+
+/// The actions created for [`EmulatedHandConfig::inputs`], in the same order
+#[derive(Resource)]
+pub struct EmulatedHandActions {
+    actions: Vec<EmulatedCurlAction>,
+}
+
+pub fn setup_emulated_hand_actions(
+    mut commands: Commands,
+    config: Res<EmulatedHandConfig>,
+    instance: Res<XrInstance>,
+    oculus_controller: Option<Res<OculusController>>,
+    mut action_sets: ResMut<ActionSets>,
+    mut bindings: ResMut<XrSuggestedBindings>,
+) {
+    init_subaction_path(&instance);
+    let hands = [subaction_path(Hand::Left), subaction_path(Hand::Right)];
+    let action_set = match instance.create_action_set("hand_emulation", "Hand Emulation", 0) {
+        Ok(action_set) => action_set,
+        Err(err) => {
+            warn!("unable to create the hand emulation actions: {}", err);
+            return;
+        }
+    };
+    let mut actions = Vec::new();
+    for input in config.inputs.iter() {
+        let action = match input.kind {
+            EmulatedCurlInputKind::Value => action_set
+                .create_action::<f32>(&input.name, &input.name, &hands)
+                .map(|action| {
+                    for (profile, path) in input.bindings.iter() {
+                        bindings.add_handed(profile, &action, path);
+                    }
+                    EmulatedCurlAction::Value(action)
+                }),
+            EmulatedCurlInputKind::Touch => action_set
+                .create_action::<bool>(&input.name, &input.name, &hands)
+                .map(|action| {
+                    for (profile, path) in input.bindings.iter() {
+                        bindings.add_handed(profile, &action, path);
+                    }
+                    EmulatedCurlAction::Touch(action)
+                }),
+        };
+        match action {
+            Ok(action) => actions.push(action),
+            Err(err) => {
+                warn!(
+                    "unable to create hand emulation action {}: {}",
+                    input.name, err
+                );
+                return;
+            }
+        }
+    }
+    //the emulated hands follow the controller, so it has to be located with these profiles too
+    if let Some(controller) = oculus_controller {
+        for profile in config.pose_profiles.iter() {
+            bindings.add_handed(profile, &controller.grip_pose, "input/grip/pose");
+            bindings.add_handed(profile, &controller.aim_pose, "input/aim/pose");
+        }
+    }
+    action_sets.0.push(action_set);
+    commands.insert_resource(EmulatedHandActions { actions });
+}
+
+pub fn update_emulated_finger_curls(
+    time: Res<Time>,
+    config: Res<EmulatedHandConfig>,
+    actions: Option<Res<EmulatedHandActions>>,
+    session: Res<XrSession>,
+    mut hands: Query<(&Hand, &mut EmulatedFingerCurls), With<OpenXRHand>>,
+) {
+    let actions = match actions {
+        Some(actions) => actions,
+        None => return,
+    };
+    for (hand, mut curls) in hands.iter_mut() {
+        let mut target = [0.0f32; 5];
+        for (input, action) in config.inputs.iter().zip(actions.actions.iter()) {
+            let value = match action {
+                EmulatedCurlAction::Value(action) => action
+                    .state(&session, subaction_path(*hand))
+                    .map_or(0.0, |state| match state.is_active {
+                        true => state.current_state,
+                        false => 0.0,
+                    }),
+                EmulatedCurlAction::Touch(action) => action
+                    .state(&session, subaction_path(*hand))
+                    .map_or(0.0, |state| match state.is_active && state.current_state {
+                        true => 1.0,
+                        false => 0.0,
+                    }),
+            };
+            for finger in input.fingers.iter() {
+                let curl = &mut target[finger.index()];
+                *curl = curl.max(value.clamp(0.0, 1.0) * input.curl);
+            }
+        }
+        let max_step = config.curl_speed * time.delta_seconds();
+        for (curl, target) in curls.0.iter_mut().zip(target) {
+            *curl = match config.curl_speed > 0.0 {
+                true => *curl + (target - *curl).clamp(-max_step, max_step),
+                false => target,
+            };
+        }
+    }
+}
+
+/// The joints of a pose asset relative to the controller grip, mirrored if needed
+pub fn pose_in_grip_space(pose: &HandPose, hand: Hand, wrist_offset: Transform) -> [Transform; 26] {
+    let mirror = pose.hand != hand.into();
+    let wrist_offset = match hand {
+        Hand::Right => wrist_offset,
+        Hand::Left => mirror_transform(wrist_offset),
+    };
+    let mut joints = [Transform::IDENTITY; 26];
+    for (joint, pose_joint) in joints.iter_mut().zip(pose.joints.iter()) {
+        let local = Transform {
+            translation: Vec3::from_array(pose_joint.position),
+            rotation: Quat::from_array(pose_joint.rotation),
+            ..default()
+        };
+        let local = match mirror {
+            true => mirror_transform(local),
+            false => local,
+        };
+        *joint = wrist_offset.mul_transform(local);
+    }
+    joints
+}
+
+/// mirrors a transform on the YZ plane, which turns a joint of one hand into the other hand
+pub fn mirror_transform(transform: Transform) -> Transform {
+    let rotation = transform.rotation;
+    Transform {
+        translation: transform.translation * Vec3::new(-1.0, 1.0, 1.0),
+        rotation: Quat::from_xyzw(rotation.x, -rotation.y, -rotation.z, rotation.w),
+        scale: transform.scale,
+    }
+}
+
+/// Blends every finger between the open and closed pose.
+///
+/// The joint rotations are blended and the positions rebuilt from the bone lengths of the
+/// open pose, so the fingers bend instead of shrinking towards the palm.
+pub fn blend_hand_poses(
+    open: &[Transform; 26],
+    closed: &[Transform; 26],
+    curls: &EmulatedFingerCurls,
+) -> [Transform; 26] {
+    let mut joints = *open;
+    for finger in Finger::all() {
+        let curl = curls.get(finger);
+        let bones = finger.bones();
+        let first = bones[0].get_index_from_bone();
+        joints[first].rotation = open[first].rotation.slerp(closed[first].rotation, curl);
+        for pair in bones.windows(2) {
+            let (parent, child) = (pair[0].get_index_from_bone(), pair[1].get_index_from_bone());
+            let bone = open[parent].rotation.inverse()
+                * (open[child].translation - open[parent].translation);
+            joints[child].translation = joints[parent].translation + joints[parent].rotation * bone;
+            joints[child].rotation = open[child].rotation.slerp(closed[child].rotation, curl);
+        }
+    }
+    joints
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+
+use super::{
+    hand::{update_hand_rigs, HandBone, HandJoints, HandTrackingAim, OpenXRHand},
+    interactions::{
+        InteractionEvent, XRInteractable, XRInteractableState, XRInteractorState, XRSelection,
+        XrHoverTarget, XrInteractableShape, XrInteractionSet,
+    },
+    interactor_input::XrInteractorInput,
+    trackers::{head_transform, AimPose, OpenXREyes, OpenXRTrackingRoot},
+    Hand,
+};
+
+/// Lets tracked and emulated hands use the interactables with [`XRPokeInteractor`]s and
+/// [`XRHandRayInteractor`]s.
+///
+/// Both send the usual [`InteractionEvent`]s, so this needs the
+/// [`XrInteractionPlugin`](super::interactions::XrInteractionPlugin).
+#[derive(Default)]
+pub struct XrHandInteractionPlugin;
+
+impl Plugin for XrHandInteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandInteractionConfig>().add_systems(
+            Update,
+            (
+                (update_poke_interactors, update_hand_ray_interactors)
+                    .after(update_hand_rigs)
+                    .before(XrInteractionSet::Detect),
+                poke_interactions.in_set(XrInteractionSet::Detect),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct HandInteractionConfig {
+    /// thumb and index tip closer than this in meters start a pinch
+    pub pinch_start_distance: f32,
+    /// a pinch ends once thumb and index tip are further apart than this, larger than
+    /// `pinch_start_distance` so the selection doesn't flicker
+    pub pinch_end_distance: f32,
+    /// where the right shoulder is relative to the head, mirrored for the left one. The hand
+    /// ray points from there through the hand when the runtime doesn't compute an aim
+    pub shoulder_offset: Vec3,
+}
+
+impl Default for HandInteractionConfig {
+    fn default() -> Self {
+        Self {
+            pinch_start_distance: 0.02,
+            pinch_end_distance: 0.035,
+            shoulder_offset: Vec3::new(0.18, -0.15, 0.05),
+        }
+    }
+}
+
+/// Presses interactables with the index finger tip of `hand`.
+///
+/// Spawn it with an [`XRInteractorState`], an [`XRSelection`](super::interactions::XRSelection),
+/// an [`XrHoverTarget`](super::interactions::XrHoverTarget) and a [`TransformBundle`] but without
+/// a parent, the transform is moved to the finger tip.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XRPokeInteractor {
+    pub hand: Hand,
+    /// how far the finger tip has to be pushed into an interactable to select it
+    pub press_depth: f32,
+    /// interactables closer than this to the finger tip are hovered
+    pub hover_distance: f32,
+}
+
+impl XRPokeInteractor {
+    pub fn new(hand: Hand) -> Self {
+        Self {
+            hand,
+            press_depth: 0.01,
+            hover_distance: 0.05,
+        }
+    }
+}
+
+/// Points an [`XRRayInteractor`](super::interactions::XRRayInteractor) from `hand`, pinching
+/// selects.
+///
+/// Spawn it with an [`XrRayInteractorBundle`](super::interactions::XrRayInteractorBundle). The
+/// ray comes from `XR_FB_hand_tracking_aim` when the runtime supports it, otherwise it points
+/// from the estimated shoulder through the hand. Pinching doesn't select with an
+/// [`XrInteractorInput`] on the interactor.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XRHandRayInteractor {
+    pub hand: Hand,
+    pinching: bool,
+}
+
+impl XRHandRayInteractor {
+    pub fn new(hand: Hand) -> Self {
+        Self {
+            hand,
+            pinching: false,
+        }
+    }
+
+    pub fn is_pinching(&self) -> bool {
+        self.pinching
+    }
+}
+
+pub fn update_poke_interactors(
+    hand_joints: HandJoints,
+    mut pokes: Query<(&XRPokeInteractor, &mut Transform, &mut GlobalTransform), Without<HandBone>>,
+) {
+    for (poke, mut transform, mut global_transform) in pokes.iter_mut() {
+        if let Some(tip) = hand_joints.get(poke.hand, HandBone::IndexTip) {
+            *transform = tip.compute_transform();
+            *global_transform = *tip;
+        }
+    }
+}
+
+pub fn update_hand_ray_interactors(
+    config: Res<HandInteractionConfig>,
+    hand_joints: HandJoints,
+    tracking_aims: Query<(&Hand, &HandTrackingAim), With<OpenXRHand>>,
+    eyes: Query<&GlobalTransform, OpenXREyes>,
+    tracking_root: Query<&GlobalTransform, With<OpenXRTrackingRoot>>,
+    mut rays: Query<(
+        &mut XRHandRayInteractor,
+        &mut AimPose,
+        &mut XRInteractorState,
+        Option<&XrInteractorInput>,
+    )>,
+) {
+    let root = match tracking_root.get_single() {
+        Ok(root) => root.compute_transform(),
+        Err(_) => return,
+    };
+    //only the heading of the head matters for the shoulders
+    let head = head_transform(&eyes).map(|head| {
+        let forward = head.forward();
+        Transform::from_translation(head.translation)
+            .with_rotation(Quat::from_rotation_y(f32::atan2(-forward.x, -forward.z)))
+    });
+    for (mut ray, mut aim, mut state, input) in rays.iter_mut() {
+        let tracking_aim = tracking_aims
+            .iter()
+            .find(|(hand, _)| **hand == ray.hand)
+            .map(|(_, tracking_aim)| tracking_aim);
+        let pinching = match tracking_aim {
+            Some(tracking_aim) => {
+                *aim = AimPose(tracking_aim.pose);
+                tracking_aim.index_pinching && !tracking_aim.system_gesture
+            }
+            None => {
+                let (joints, head) = match (hand_joints.transforms(ray.hand), head) {
+                    (Some(joints), Some(head)) => (joints, head),
+                    _ => continue,
+                };
+                let joint = |bone: HandBone| joints[bone.get_index_from_bone()].translation;
+                let side = match ray.hand {
+                    Hand::Left => -1.0,
+                    Hand::Right => 1.0,
+                };
+                let shoulder =
+                    head.transform_point(config.shoulder_offset * Vec3::new(side, 1.0, 1.0));
+                let origin = joint(HandBone::IndexProximal);
+                let direction = (origin - shoulder).normalize_or_zero();
+                //the aim pose is in the tracking space
+                let inverse_rotation = root.rotation.inverse();
+                *aim = AimPose(
+                    Transform::from_translation(inverse_rotation * (origin - root.translation))
+                        .looking_to(inverse_rotation * direction, Vec3::Y),
+                );
+                let pinch_distance = joint(HandBone::ThumbTip).distance(joint(HandBone::IndexTip));
+                match ray.pinching {
+                    true => pinch_distance < config.pinch_end_distance,
+                    false => pinch_distance < config.pinch_start_distance,
+                }
+            }
+        };
+        ray.pinching = pinching;
+        //an XrInteractorInput sets the state from its own inputs instead
+        if input.is_none() {
+            *state = match pinching {
+                true => XRInteractorState::Selecting,
+                false => XRInteractorState::Idle,
+            };
+        }
+    }
+}
+
+pub fn poke_interactions(
+    interactable_query: Query<
+        (
+            &GlobalTransform,
+            Entity,
+            Option<&XrInteractableShape>,
+            Option<&Aabb>,
+        ),
+        With<XRInteractable>,
+    >,
+    mut poke_query: Query<
+        (
+            &GlobalTransform,
+            &XRPokeInteractor,
+            &mut XRInteractorState,
+            Entity,
+        ),
+        Without<XRInteractable>,
+    >,
+    mut writer: EventWriter<InteractionEvent>,
+) {
+    for (tip, poke, mut state, poke_entity) in poke_query.iter_mut() {
+        let mut pressing = false;
+        for (interactable, interactable_entity, shape, aabb) in interactable_query.iter() {
+            let hit = shape.copied().unwrap_or_default().closest_point(
+                interactable,
+                aabb,
+                tip.translation(),
+            );
+            //how far the finger tip is inside of the interactable
+            let depth = -hit.distance;
+            let interactable_state = if depth >= poke.press_depth {
+                pressing = true;
+                XRInteractableState::Select
+            } else if depth > -poke.hover_distance {
+                XRInteractableState::Hover
+            } else {
+                continue;
+            };
+            writer.send(InteractionEvent {
+                interactor: poke_entity,
+                interactable: interactable_entity,
+                interactable_state,
+                hit: Some(hit),
+            });
+        }
+        *state = match pressing {
+            true => XRInteractorState::Selecting,
+            false => XRInteractorState::Idle,
+        };
+    }
+}
@@ -0,0 +1,357 @@
+use std::ptr;
+
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::render::mesh::skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
+use bevy::render::mesh::{Indices, VertexAttributeValues};
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::render::view::NoFrustumCulling;
+use bevy::transform::TransformSystem;
+use openxr as xr;
+
+use crate::{input::pose_to_transform, resources::XrInstance};
+
+use super::{
+    hand::{HandBone, HandJoints},
+    handtracking::HandTrackingTracker,
+    Hand,
+};
+
+/// Drives skinned hand meshes with the hand bone entities.
+///
+/// Spawn a glTF scene with a [`XrHandMesh`] to use your own model, or an entity with a
+/// [`XrRuntimeHandMesh`] to use the mesh the runtime provides with `XR_FB_hand_tracking_mesh`.
+#[derive(Default)]
+pub struct XrHandMeshPlugin;
+
+impl Plugin for XrHandMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (bind_hand_meshes, load_runtime_hand_meshes))
+            .add_systems(
+                PostUpdate,
+                drive_hand_meshes.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// A skinned hand model, put this on the root of the spawned glTF scene.
+///
+/// The rest pose of the model has to be a flat open hand, palm down with the fingers
+/// pointing along `rest_rotation * -Z`, the joints are matched to [`HandBone`]s by name.
+#[derive(Component, Clone, Debug)]
+pub struct XrHandMesh {
+    pub hand: Hand,
+    /// the model is of the other hand and gets mirrored, the triangle winding flips with that
+    /// so the materials of the model are made double sided
+    pub mirror: bool,
+    /// rotates the rest pose of the model onto the OpenXR joint axes
+    pub rest_rotation: Quat,
+    pub joint_names: HandMeshJointNames,
+}
+
+impl XrHandMesh {
+    pub fn new(hand: Hand) -> Self {
+        Self {
+            hand,
+            mirror: false,
+            rest_rotation: Quat::IDENTITY,
+            joint_names: HandMeshJointNames::default(),
+        }
+    }
+}
+
+/// Node names of the model joints, in the order of [`HandBone::get_all_bones`]
+#[derive(Clone, Debug)]
+pub struct HandMeshJointNames(pub [String; 26]);
+
+impl HandMeshJointNames {
+    /// the names of the [`HandBone`] variants with a prefix, e.g. `L_IndexTip`
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self(HandBone::get_all_bones().map(|bone| format!("{}{:?}", prefix, bone)))
+    }
+}
+
+impl Default for HandMeshJointNames {
+    fn default() -> Self {
+        Self::with_prefix("")
+    }
+}
+
+/// The model joints of a bound [`XrHandMesh`]
+#[derive(Component, Clone, Debug)]
+pub struct XrHandMeshJoints {
+    /// joint entity, the bone driving it and its rotation relative to that bone
+    pub joints: Vec<(Entity, HandBone, Quat)>,
+}
+
+/// Replaced with the skinned hand mesh of the runtime once hand tracking is available
+#[derive(Component, Clone, Debug)]
+pub struct XrRuntimeHandMesh {
+    pub hand: Hand,
+    pub material: Handle<StandardMaterial>,
+}
+
+const MIRROR_X: Vec3 = Vec3::new(-1.0, 1.0, 1.0);
+
+fn mirror_rotation(rotation: Quat) -> Quat {
+    Quat::from_xyzw(rotation.x, -rotation.y, -rotation.z, rotation.w)
+}
+
+pub fn bind_hand_meshes(
+    mut commands: Commands,
+    hand_meshes: Query<(Entity, &XrHandMesh), Without<XrHandMeshJoints>>,
+    children_query: Query<&Children>,
+    names: Query<&Name>,
+    transforms: Query<&Transform>,
+    parents: Query<&Parent>,
+    material_query: Query<&Handle<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (root, hand_mesh) in hand_meshes.iter() {
+        let mut joints = Vec::new();
+        for descendant in children_query.iter_descendants(root) {
+            let name = match names.get(descendant) {
+                Ok(name) => name.as_str(),
+                Err(_) => continue,
+            };
+            let bone = match hand_mesh.joint_names.0.iter().position(|n| n == name) {
+                Some(index) => HandBone::get_all_bones()[index],
+                None => continue,
+            };
+            //rest rotation of the joint relative to the scene root
+            let mut rest = Quat::IDENTITY;
+            let mut entity = descendant;
+            while entity != root {
+                if let Ok(transform) = transforms.get(entity) {
+                    rest = transform.rotation * rest;
+                }
+                entity = match parents.get(entity) {
+                    Ok(parent) => parent.get(),
+                    Err(_) => break,
+                };
+            }
+            let rest = hand_mesh.rest_rotation * rest;
+            let rest = match hand_mesh.mirror {
+                true => mirror_rotation(rest),
+                false => rest,
+            };
+            joints.push((descendant, bone, rest));
+        }
+        //the scene isn't spawned yet
+        if joints.is_empty() {
+            continue;
+        }
+        if joints.len() < 26 {
+            warn!(
+                "only found {} of 26 joints in the {:?} hand mesh",
+                joints.len(),
+                hand_mesh.hand
+            );
+        }
+        if hand_mesh.mirror {
+            for descendant in children_query.iter_descendants(root) {
+                let material = match material_query.get(descendant) {
+                    Ok(handle) => materials.get(handle).cloned(),
+                    Err(_) => continue,
+                };
+                if let Some(mut material) = material {
+                    material.double_sided = true;
+                    material.cull_mode = None;
+                    commands.entity(descendant).insert(materials.add(material));
+                }
+            }
+        }
+        commands.entity(root).insert(XrHandMeshJoints { joints });
+    }
+}
+
+pub fn drive_hand_meshes(
+    hand_meshes: Query<(&XrHandMesh, &XrHandMeshJoints, &GlobalTransform)>,
+    hand_joints: HandJoints,
+    mut joint_query: Query<&mut GlobalTransform, (Without<HandBone>, Without<XrHandMeshJoints>)>,
+) {
+    for (hand_mesh, joints, root_transform) in hand_meshes.iter() {
+        //the bones are in meters, the model keeps the scale of its root, e.g. for centimeters
+        let (scale, _, _) = root_transform.to_scale_rotation_translation();
+        let bones = match hand_joints.transforms(hand_mesh.hand) {
+            Some(bones) => bones,
+            None => continue,
+        };
+        for (joint, bone, rest) in joints.joints.iter() {
+            let bone_transform = bones[bone.get_index_from_bone()];
+            let mut joint_transform = match joint_query.get_mut(*joint) {
+                Ok(joint_transform) => joint_transform,
+                Err(_) => continue,
+            };
+            let mut affine = Affine3A::from_scale_rotation_translation(
+                scale,
+                bone_transform.rotation * *rest,
+                bone_transform.translation,
+            );
+            if hand_mesh.mirror {
+                affine = affine * Affine3A::from_scale(MIRROR_X);
+            }
+            *joint_transform = affine.into();
+        }
+    }
+}
+
+pub fn load_runtime_hand_meshes(
+    mut commands: Commands,
+    runtime_meshes: Query<(Entity, &XrRuntimeHandMesh), Without<Handle<Mesh>>>,
+    hand_joints: HandJoints,
+    instance: Option<Res<XrInstance>>,
+    hand_tracking: Option<Res<HandTrackingTracker>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    if runtime_meshes.is_empty() {
+        return;
+    }
+    let (instance, hand_tracking) = match (instance, hand_tracking) {
+        (Some(instance), Some(hand_tracking)) => (instance, hand_tracking),
+        _ => return,
+    };
+    for (entity, runtime_mesh) in runtime_meshes.iter() {
+        let joints = match hand_joints.entities(runtime_mesh.hand) {
+            Some(joints) => joints.0,
+            None => continue,
+        };
+        let data = match get_runtime_hand_mesh(&instance, hand_tracking.tracker(runtime_mesh.hand))
+        {
+            Ok(data) => data,
+            Err(err) => {
+                warn!(
+                    "unable to get the {:?} hand mesh from the runtime: {}",
+                    runtime_mesh.hand, err
+                );
+                commands.entity(entity).remove::<XrRuntimeHandMesh>();
+                continue;
+            }
+        };
+        let bindposes = data
+            .joint_bind_poses
+            .iter()
+            .map(|pose| pose_to_transform(*pose).compute_matrix().inverse())
+            .collect::<Vec<Mat4>>();
+        commands.entity(entity).insert((
+            meshes.add(data.mesh),
+            runtime_mesh.material.clone(),
+            SkinnedMesh {
+                inverse_bindposes: inverse_bindposes.add(bindposes.into()),
+                joints: joints[..data.joint_bind_poses.len().min(26)].to_vec(),
+            },
+            //the bounds of the rest pose don't follow the tracked hand
+            NoFrustumCulling,
+        ));
+    }
+}
+
+struct RuntimeHandMesh {
+    mesh: Mesh,
+    joint_bind_poses: Vec<xr::Posef>,
+}
+
+fn cvt(result: xr::sys::Result) -> xr::Result<()> {
+    match result.into_raw() >= 0 {
+        true => Ok(()),
+        false => Err(result),
+    }
+}
+
+/// reads the mesh of a hand with `xrGetHandMeshFB`
+fn get_runtime_hand_mesh(
+    instance: &xr::Instance,
+    tracker: &xr::HandTracker,
+) -> xr::Result<RuntimeHandMesh> {
+    let ext = match instance.exts().fb_hand_tracking_mesh {
+        Some(ext) => ext,
+        None => return Err(xr::sys::Result::ERROR_EXTENSION_NOT_PRESENT),
+    };
+    let mut mesh = xr::sys::HandTrackingMeshFB {
+        ty: xr::sys::HandTrackingMeshFB::TYPE,
+        next: ptr::null_mut(),
+        joint_capacity_input: 0,
+        joint_count_output: 0,
+        joint_bind_poses: ptr::null_mut(),
+        joint_radii: ptr::null_mut(),
+        joint_parents: ptr::null_mut(),
+        vertex_capacity_input: 0,
+        vertex_count_output: 0,
+        vertex_positions: ptr::null_mut(),
+        vertex_normals: ptr::null_mut(),
+        vertex_uvs: ptr::null_mut(),
+        vertex_blend_indices: ptr::null_mut(),
+        vertex_blend_weights: ptr::null_mut(),
+        index_capacity_input: 0,
+        index_count_output: 0,
+        indices: ptr::null_mut(),
+    };
+    //first call gets the sizes, the second one fills the buffers
+    cvt(unsafe { (ext.get_hand_mesh)(tracker.as_raw(), &mut mesh) })?;
+    let joint_count = mesh.joint_count_output as usize;
+    let vertex_count = mesh.vertex_count_output as usize;
+    let index_count = mesh.index_count_output as usize;
+    let mut joint_bind_poses = vec![xr::Posef::IDENTITY; joint_count];
+    let mut joint_radii = vec![0.0f32; joint_count];
+    let mut joint_parents = vec![xr::HandJoint::PALM; joint_count];
+    let mut positions = vec![xr::Vector3f::default(); vertex_count];
+    let mut normals = vec![xr::Vector3f::default(); vertex_count];
+    let mut uvs = vec![xr::Vector2f::default(); vertex_count];
+    let mut blend_indices = vec![xr::sys::Vector4sFB::default(); vertex_count];
+    let mut blend_weights = vec![xr::Vector4f::default(); vertex_count];
+    let mut indices = vec![0i16; index_count];
+    mesh.joint_capacity_input = joint_count as u32;
+    mesh.joint_bind_poses = joint_bind_poses.as_mut_ptr();
+    mesh.joint_radii = joint_radii.as_mut_ptr();
+    mesh.joint_parents = joint_parents.as_mut_ptr();
+    mesh.vertex_capacity_input = vertex_count as u32;
+    mesh.vertex_positions = positions.as_mut_ptr();
+    mesh.vertex_normals = normals.as_mut_ptr();
+    mesh.vertex_uvs = uvs.as_mut_ptr();
+    mesh.vertex_blend_indices = blend_indices.as_mut_ptr();
+    mesh.vertex_blend_weights = blend_weights.as_mut_ptr();
+    mesh.index_capacity_input = index_count as u32;
+    mesh.indices = indices.as_mut_ptr();
+    cvt(unsafe { (ext.get_hand_mesh)(tracker.as_raw(), &mut mesh) })?;
+
+    let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    bevy_mesh.insert_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions
+            .iter()
+            .map(|v| [v.x, v.y, v.z])
+            .collect::<Vec<_>>(),
+    );
+    bevy_mesh.insert_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        normals.iter().map(|v| [v.x, v.y, v.z]).collect::<Vec<_>>(),
+    );
+    bevy_mesh.insert_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        uvs.iter().map(|v| [v.x, v.y]).collect::<Vec<_>>(),
+    );
+    bevy_mesh.insert_attribute(
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        VertexAttributeValues::Uint16x4(
+            blend_indices
+                .iter()
+                .map(|i| [i.x as u16, i.y as u16, i.z as u16, i.w as u16])
+                .collect(),
+        ),
+    );
+    bevy_mesh.insert_attribute(
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        blend_weights
+            .iter()
+            .map(|w| [w.x, w.y, w.z, w.w])
+            .collect::<Vec<_>>(),
+    );
+    bevy_mesh.set_indices(Some(Indices::U16(
+        indices.iter().map(|i| *i as u16).collect(),
+    )));
+    Ok(RuntimeHandMesh {
+        mesh: bevy_mesh,
+        joint_bind_poses,
+    })
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use super::hand::{update_hand_rigs, update_hand_skeletons, HandBone, HandBoneRadius};
+use crate::XrFrameSet;
+
+/// Keeps a [`HandBoneCapsule`] on every hand joint that has a parent joint.
+///
+/// The capsules don't depend on any physics engine, enable the `rapier` feature and add
+/// [`XrHandRapierPlugin`](super::hand_rapier::XrHandRapierPlugin) to get colliders from them.
+#[derive(Default)]
+pub struct XrHandPhysicsPlugin;
+
+impl Plugin for XrHandPhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandPhysicsConfig>()
+            .add_systems(
+                Update,
+                (insert_hand_bone_capsules, update_hand_bone_capsules)
+                    .chain()
+                    .after(update_hand_rigs),
+            )
+            .add_systems(
+                PostUpdate,
+                update_hand_bone_capsules
+                    .after(update_hand_skeletons)
+                    .after(update_hand_rigs)
+                    .in_set(XrFrameSet::LateLatch),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct HandPhysicsConfig {
+    /// radius used for joints without a [`HandBoneRadius`], e.g. emulated hands
+    pub default_radius: f32,
+    /// multiplies every radius, below 1 keeps the fingers from catching on each other
+    pub radius_scale: f32,
+    /// bodies further than this from their capsule are moved there instead of being pushed,
+    /// e.g. after teleporting or when a hand starts being tracked again
+    pub snap_distance: f32,
+}
+
+impl Default for HandPhysicsConfig {
+    fn default() -> Self {
+        Self {
+            default_radius: 0.01,
+            radius_scale: 1.0,
+            snap_distance: 0.5,
+        }
+    }
+}
+
+/// The segment between a joint and its parent joint in world space
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct HandBoneCapsule {
+    /// position of the parent joint
+    pub start: Vec3,
+    /// position of this joint
+    pub end: Vec3,
+    pub radius: f32,
+}
+
+impl HandBoneCapsule {
+    pub fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
+
+    pub fn center(&self) -> Vec3 {
+        self.start.lerp(self.end, 0.5)
+    }
+
+    /// a rotation turning the y axis from `start` towards `end`, the way capsule colliders are
+    /// usually aligned
+    pub fn rotation(&self) -> Quat {
+        match (self.end - self.start).try_normalize() {
+            Some(direction) => Quat::from_rotation_arc(Vec3::Y, direction),
+            None => Quat::IDENTITY,
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.center()).with_rotation(self.rotation())
+    }
+}
+
+pub fn insert_hand_bone_capsules(
+    mut commands: Commands,
+    bones: Query<(Entity, &HandBone), (Added<HandBone>, Without<HandBoneCapsule>)>,
+) {
+    for (entity, bone) in bones.iter() {
+        if bone.get_parent().is_some() {
+            commands.entity(entity).insert(HandBoneCapsule::default());
+        }
+    }
+}
+
+pub fn update_hand_bone_capsules(
+    config: Res<HandPhysicsConfig>,
+    joints: Query<(&GlobalTransform, Option<&HandBoneRadius>), With<HandBone>>,
+    mut capsules: Query<(Entity, &Parent, &mut HandBoneCapsule)>,
+) {
+    let radius = |radius: Option<&HandBoneRadius>| {
+        radius.map_or(config.default_radius, |r| r.0) * config.radius_scale
+    };
+    for (entity, parent, mut capsule) in capsules.iter_mut() {
+        //bones are children of their parent joint, see spawn_hand_rig
+        let ((end, end_radius), (start, start_radius)) =
+            match (joints.get(entity), joints.get(parent.get())) {
+                (Ok(end), Ok(start)) => (end, start),
+                _ => continue,
+            };
+        let new = HandBoneCapsule {
+            start: start.translation(),
+            end: end.translation(),
+            radius: radius(start_radius).min(radius(end_radius)),
+        };
+        //only touch the component when it moved so change detection stays useful
+        if *capsule != new {
+            *capsule = new;
+        }
+    }
+}
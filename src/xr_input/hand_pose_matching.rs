@@ -0,0 +1,288 @@
+use std::path::PathBuf;
+
+use bevy::asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    hand::{update_hand_skeletons, HandBone, HandJoints, OpenXRHand},
+    Hand,
+};
+
+/// Loads [`HandPose`] assets from `.handpose.ron` files, captures new ones on
+/// [`CaptureHandPose`] and matches the hands against the poses in [`HandPoseMatcher`].
+#[derive(Default)]
+pub struct HandPoseMatchingPlugin;
+
+impl Plugin for HandPoseMatchingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<HandPose>()
+            .init_asset_loader::<HandPoseLoader>()
+            .init_resource::<HandPoseMatcher>()
+            .add_event::<CaptureHandPose>()
+            .add_event::<HandPoseCaptured>()
+            .add_event::<HandPoseMatched>()
+            .add_systems(
+                Update,
+                (capture_hand_poses, match_hand_poses).after(update_hand_skeletons),
+            );
+    }
+}
+
+/// A named pose of all 26 hand joints, relative to the wrist
+#[derive(Asset, TypePath, Clone, Debug, Serialize, Deserialize)]
+pub struct HandPose {
+    pub name: String,
+    /// the hand the pose was recorded with, the other hand is matched mirrored
+    pub hand: HandPoseHand,
+    /// in the order of [`HandBone::get_all_bones`]
+    pub joints: Vec<HandPoseJoint>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandPoseHand {
+    Left,
+    Right,
+}
+
+impl From<Hand> for HandPoseHand {
+    fn from(hand: Hand) -> Self {
+        match hand {
+            Hand::Left => HandPoseHand::Left,
+            Hand::Right => HandPoseHand::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HandPoseJoint {
+    pub position: [f32; 3],
+    /// quaternion as x, y, z, w
+    pub rotation: [f32; 4],
+}
+
+impl HandPose {
+    /// creates a pose from the world space transforms of all hand bones
+    pub fn from_bones(name: impl Into<String>, hand: Hand, bones: &[Transform; 26]) -> Self {
+        let wrist = bones[HandBone::Wrist.get_index_from_bone()];
+        let to_wrist = wrist.compute_affine().inverse();
+        let joints = bones
+            .iter()
+            .map(|bone| HandPoseJoint {
+                position: to_wrist.transform_point3(bone.translation).to_array(),
+                rotation: (wrist.rotation.inverse() * bone.rotation).to_array(),
+            })
+            .collect();
+        Self {
+            name: name.into(),
+            hand: hand.into(),
+            joints,
+        }
+    }
+
+    /// joint positions relative to the wrist, mirrored if `hand` isn't the recorded one
+    pub fn joint_positions(&self, hand: Hand) -> impl Iterator<Item = Vec3> + '_ {
+        let mirror = self.hand != hand.into();
+        self.joints.iter().map(move |joint| {
+            let position = Vec3::from_array(joint.position);
+            match mirror {
+                true => position * Vec3::new(-1.0, 1.0, 1.0),
+                false => position,
+            }
+        })
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+}
+
+#[derive(Default)]
+pub struct HandPoseLoader;
+
+impl AssetLoader for HandPoseLoader {
+    type Asset = HandPose;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let pose = ron::de::from_bytes::<HandPose>(&bytes)?;
+            if pose.joints.len() != 26 {
+                anyhow::bail!(
+                    "hand pose {} has {} joints instead of 26",
+                    pose.name,
+                    pose.joints.len()
+                );
+            }
+            Ok(pose)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["handpose.ron"]
+    }
+}
+
+pub struct RegisteredHandPose {
+    pub pose: Handle<HandPose>,
+    /// average distance of the joints to the pose in meters up to which the pose matches
+    pub tolerance: f32,
+}
+
+/// The poses the hands are matched against
+#[derive(Resource, Default)]
+pub struct HandPoseMatcher {
+    pub poses: Vec<RegisteredHandPose>,
+}
+
+impl HandPoseMatcher {
+    /// `tolerance` has to be above 0, poses with any other tolerance could never match
+    pub fn register(&mut self, pose: Handle<HandPose>, tolerance: f32) {
+        if tolerance.is_nan() || tolerance <= 0.0 {
+            warn!(
+                "not registering hand pose with tolerance {}, it has to be above 0",
+                tolerance
+            );
+            return;
+        }
+        self.poses.push(RegisteredHandPose { pose, tolerance });
+    }
+}
+
+/// The registered pose a hand currently matches best
+#[derive(Component, Clone, Debug, Default)]
+pub struct MatchedHandPose {
+    pub pose: Option<Handle<HandPose>>,
+    /// 1 for a perfect match, 0 at the tolerance of the pose
+    pub score: f32,
+}
+
+/// Sent when a hand starts matching a registered pose
+#[derive(Event, Clone, Debug)]
+pub struct HandPoseMatched {
+    pub entity: Entity,
+    pub hand: Hand,
+    pub pose: Handle<HandPose>,
+    pub name: String,
+    pub score: f32,
+}
+
+/// Send this to record the current pose of a hand as a [`HandPose`] asset
+#[derive(Event, Clone, Debug)]
+pub struct CaptureHandPose {
+    pub hand: Hand,
+    pub name: String,
+    /// also write the pose as RON to this file
+    pub path: Option<PathBuf>,
+}
+
+/// Sent with the asset created for a [`CaptureHandPose`]
+#[derive(Event, Clone, Debug)]
+pub struct HandPoseCaptured {
+    pub hand: Hand,
+    pub pose: Handle<HandPose>,
+}
+
+pub fn capture_hand_poses(
+    mut capture_events: EventReader<CaptureHandPose>,
+    mut captured_events: EventWriter<HandPoseCaptured>,
+    hand_joints: HandJoints,
+    mut poses: ResMut<Assets<HandPose>>,
+) {
+    for event in capture_events.read() {
+        let bones = match hand_joints.transforms(event.hand) {
+            Some(bones) => bones,
+            None => {
+                warn!("no {:?} hand to capture {} from", event.hand, event.name);
+                continue;
+            }
+        };
+        let pose = HandPose::from_bones(event.name.clone(), event.hand, &bones);
+        if let Some(path) = &event.path {
+            match pose.to_ron() {
+                Ok(ron) => match std::fs::write(path, ron) {
+                    Ok(_) => info!("saved hand pose {} to {}", pose.name, path.display()),
+                    Err(err) => warn!("unable to save hand pose {}: {}", pose.name, err),
+                },
+                Err(err) => warn!("unable to serialize hand pose {}: {}", pose.name, err),
+            }
+        }
+        captured_events.send(HandPoseCaptured {
+            hand: event.hand,
+            pose: poses.add(pose),
+        });
+    }
+}
+
+pub fn match_hand_poses(
+    mut commands: Commands,
+    matcher: Res<HandPoseMatcher>,
+    poses: Res<Assets<HandPose>>,
+    hand_joints: HandJoints,
+    mut hand_query: Query<(Entity, &Hand, Option<&mut MatchedHandPose>), With<OpenXRHand>>,
+    mut matched_events: EventWriter<HandPoseMatched>,
+) {
+    for (entity, hand, matched) in hand_query.iter_mut() {
+        let bones = match hand_joints.transforms(*hand) {
+            Some(bones) => bones,
+            None => continue,
+        };
+        let wrist = bones[HandBone::Wrist.get_index_from_bone()];
+        let to_wrist = wrist.compute_affine().inverse();
+        let live: Vec<Vec3> = bones
+            .iter()
+            .map(|bone| to_wrist.transform_point3(bone.translation))
+            .collect();
+
+        let mut best: Option<(&RegisteredHandPose, &HandPose, f32)> = None;
+        for registered in matcher.poses.iter() {
+            let pose = match poses.get(&registered.pose) {
+                Some(pose) => pose,
+                None => continue,
+            };
+            let distance = pose
+                .joint_positions(*hand)
+                .zip(live.iter())
+                .map(|(expected, actual)| expected.distance(*actual))
+                .sum::<f32>()
+                / live.len() as f32;
+            let score = 1.0 - distance / registered.tolerance;
+            if score > 0.0 && best.map_or(true, |(_, _, best_score)| score > best_score) {
+                best = Some((registered, pose, score));
+            }
+        }
+
+        let previous = matched.as_ref().and_then(|m| m.pose.clone());
+        let current = MatchedHandPose {
+            pose: best.map(|(registered, _, _)| registered.pose.clone()),
+            score: best.map_or(0.0, |(_, _, score)| score),
+        };
+        if let Some((registered, pose, score)) = best {
+            if previous.as_ref() != Some(&registered.pose) {
+                matched_events.send(HandPoseMatched {
+                    entity,
+                    hand: *hand,
+                    pose: registered.pose.clone(),
+                    name: pose.name.clone(),
+                    score,
+                });
+            }
+        }
+        match matched {
+            Some(mut matched) => *matched = current,
+            None => {
+                commands.entity(entity).insert(current);
+            }
+        }
+    }
+}
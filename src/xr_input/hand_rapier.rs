@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider, RigidBody, Velocity};
+
+use super::hand_physics::{update_hand_bone_capsules, HandBoneCapsule, HandPhysicsConfig};
+
+/// Gives every [`HandBoneCapsule`] a kinematic rapier body that follows it.
+///
+/// The bodies are moved through their velocity instead of their transform, so whatever they
+/// push gets the speed of the hand. Needs [`XrHandPhysicsPlugin`](super::hand_physics::XrHandPhysicsPlugin)
+/// and the `rapier` feature.
+#[derive(Default)]
+pub struct XrHandRapierPlugin;
+
+impl Plugin for XrHandRapierPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_hand_bone_colliders, follow_hand_bone_capsules)
+                .chain()
+                .after(update_hand_bone_capsules),
+        );
+    }
+}
+
+/// A rapier body following the [`HandBoneCapsule`] of `bone`
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HandBoneCollider {
+    pub bone: Entity,
+    half_height: f32,
+    radius: f32,
+}
+
+pub fn spawn_hand_bone_colliders(
+    mut commands: Commands,
+    config: Res<HandPhysicsConfig>,
+    capsules: Query<(Entity, &HandBoneCapsule), Added<HandBoneCapsule>>,
+) {
+    for (bone, capsule) in capsules.iter() {
+        let half_height = capsule.length() * 0.5;
+        //new capsules haven't been placed yet, the collider gets its real size once they are
+        let radius = capsule.radius.max(config.default_radius);
+        commands.spawn((
+            TransformBundle::from_transform(capsule.transform()),
+            RigidBody::KinematicVelocityBased,
+            Collider::capsule_y(half_height, radius),
+            Velocity::zero(),
+            HandBoneCollider {
+                bone,
+                half_height,
+                radius,
+            },
+        ));
+    }
+}
+
+pub fn follow_hand_bone_capsules(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<HandPhysicsConfig>,
+    capsules: Query<&HandBoneCapsule>,
+    mut bodies: Query<(
+        Entity,
+        &mut HandBoneCollider,
+        &mut Collider,
+        &mut Transform,
+        &mut Velocity,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut body, mut collider, mut transform, mut velocity) in bodies.iter_mut() {
+        let capsule = match capsules.get(body.bone) {
+            Ok(capsule) => capsule,
+            Err(_) => {
+                //the hand was despawned
+                commands.entity(entity).despawn();
+                continue;
+            }
+        };
+        let half_height = capsule.length() * 0.5;
+        if (body.half_height - half_height).abs() > f32::EPSILON
+            || (body.radius - capsule.radius).abs() > f32::EPSILON
+        {
+            *collider = Collider::capsule_y(half_height, capsule.radius);
+            body.half_height = half_height;
+            body.radius = capsule.radius;
+        }
+        let target = capsule.transform();
+        if delta <= 0.0 || transform.translation.distance(target.translation) > config.snap_distance
+        {
+            *transform = target;
+            *velocity = Velocity::zero();
+            continue;
+        }
+        velocity.linvel = (target.translation - transform.translation) / delta;
+        let mut rotation = target.rotation * transform.rotation.inverse();
+        //take the short way around
+        if rotation.w < 0.0 {
+            rotation = -rotation;
+        }
+        let (axis, angle) = rotation.to_axis_angle();
+        velocity.angvel = axis * angle / delta;
+    }
+}
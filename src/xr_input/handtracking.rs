@@ -1,30 +1,153 @@
 use std::mem::MaybeUninit;
+use std::ptr;
 
 use bevy::prelude::*;
-use openxr::{HandJointLocationEXT, HandTracker, Result};
+use openxr::{HandJointLocationEXT, HandJointVelocityEXT, HandTracker};
 
 use crate::{
+    error::XrError,
     input::XrInput,
-    resources::{XrFrameState, XrFrameWaiter, XrSession},
+    resources::{XrFrameState, XrFrameWaiter, XrInstance, XrSession},
 };
 
-use super::hand::HandBone;
+use super::{
+    hand::{HandBone, HandInputSource, HandInputSourceMode},
+    Hand,
+};
+
+/// Tracks hands with `XR_EXT_hand_tracking` when the runtime supports it.
+///
+/// Reports the result as [`HandTrackingSupport`] and only inserts the [`HandTrackingTracker`]
+/// when hands can actually be tracked, otherwise the hands stay emulated from the controllers.
+#[derive(Default)]
+pub struct HandTrackingPlugin;
+
+impl Plugin for HandTrackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HandJointsMotionRange>()
+            .add_systems(Startup, setup_hand_tracking);
+    }
+}
+
+/// How tracked fingers move while the hand holds a controller, needs
+/// `XR_EXT_hand_joints_motion_range` and is ignored without it
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HandJointsMotionRange {
+    /// the joints follow the real hand, fingers curl into the controller
+    #[default]
+    Unobstructed,
+    /// the joints stop where the controller is, e.g. to hold a virtual controller or
+    /// keep physics hands from pushing into it
+    ConformingToController,
+}
+
+impl HandJointsMotionRange {
+    fn to_raw(self) -> openxr::sys::HandJointsMotionRangeEXT {
+        match self {
+            HandJointsMotionRange::Unobstructed => {
+                openxr::sys::HandJointsMotionRangeEXT::UNOBSTRUCTED
+            }
+            HandJointsMotionRange::ConformingToController => {
+                openxr::sys::HandJointsMotionRangeEXT::CONFORMING_TO_CONTROLLER
+            }
+        }
+    }
+}
+
+/// Whether hand joints can be located on this runtime and system
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandTrackingSupport {
+    Supported,
+    /// `XR_EXT_hand_tracking` is not enabled on the instance
+    ExtensionMissing,
+    /// the extension is enabled but the system can't track hands
+    SystemUnsupported,
+    /// the runtime failed to create the hand trackers
+    TrackerCreationFailed(openxr::sys::Result),
+}
+
+impl HandTrackingSupport {
+    pub fn is_supported(&self) -> bool {
+        *self == HandTrackingSupport::Supported
+    }
+}
+
+pub fn setup_hand_tracking(
+    mut commands: Commands,
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    input_source_mode: Option<ResMut<HandInputSourceMode>>,
+) {
+    let support = if instance.exts().ext_hand_tracking.is_none() {
+        HandTrackingSupport::ExtensionMissing
+    } else {
+        let system_supported = instance
+            .system(openxr::FormFactor::HEAD_MOUNTED_DISPLAY)
+            .and_then(|system| instance.supports_hand_tracking(system));
+        match system_supported {
+            Ok(true) => match HandTrackingTracker::new(&session) {
+                Ok(tracker) => {
+                    commands.insert_resource(tracker);
+                    HandTrackingSupport::Supported
+                }
+                Err(err) => HandTrackingSupport::TrackerCreationFailed(err),
+            },
+            Ok(false) => HandTrackingSupport::SystemUnsupported,
+            Err(err) => HandTrackingSupport::TrackerCreationFailed(err),
+        }
+    };
+    info!("hand tracking support: {:?}", support);
+    if !support.is_supported() {
+        if let Some(mut mode) = input_source_mode {
+            if *mode == HandInputSourceMode::Fixed(HandInputSource::OpenXr) {
+                info!("falling back to emulated hands");
+                *mode = HandInputSourceMode::Fixed(HandInputSource::Emulated);
+            }
+        }
+    }
+    commands.insert_resource(support);
+}
 
 #[derive(Resource)]
 pub struct HandTrackingTracker {
     left_hand: HandTracker,
     right_hand: HandTracker,
+    ext: openxr::raw::HandTrackingEXT,
+    motion_range_supported: bool,
+    aim_supported: bool,
 }
 
 impl HandTrackingTracker {
-    pub fn new(session: &XrSession) -> Result<HandTrackingTracker> {
+    pub fn new(session: &XrSession) -> openxr::Result<HandTrackingTracker> {
+        let exts = session.instance().exts();
+        let ext = match exts.ext_hand_tracking {
+            Some(ext) => ext,
+            None => return Err(openxr::sys::Result::ERROR_EXTENSION_NOT_PRESENT),
+        };
         let left = session.create_hand_tracker(openxr::HandEXT::LEFT)?;
         let right = session.create_hand_tracker(openxr::HandEXT::RIGHT)?;
         Ok(HandTrackingTracker {
             left_hand: left,
             right_hand: right,
+            ext,
+            motion_range_supported: exts.ext_hand_joints_motion_range.is_some(),
+            aim_supported: exts.fb_hand_tracking_aim.is_some(),
         })
     }
+    /// whether [`HandJointsMotionRange`] has any effect
+    pub fn motion_range_supported(&self) -> bool {
+        self.motion_range_supported
+    }
+    /// whether [`HandJointsState::aim`] is reported, needs `XR_FB_hand_tracking_aim`
+    pub fn aim_supported(&self) -> bool {
+        self.aim_supported
+    }
+    pub fn tracker(&self, hand: Hand) -> &HandTracker {
+        match hand {
+            Hand::Left => &self.left_hand,
+            Hand::Right => &self.right_hand,
+        }
+    }
     pub fn get_ref<'a>(
         &'a self,
         input: &'a XrInput,
@@ -46,15 +169,106 @@ pub struct HandTrackingRef<'a> {
 
 // pub type HandJoints = [(HandJointLocationEXT, HandBone); 26];
 
+/// Locations and velocities of all joints of a hand, in the stage space
+#[derive(Clone, Copy)]
+pub struct HandJointsState {
+    pub locations: [HandJointLocationEXT; 26],
+    pub velocities: [HandJointVelocityEXT; 26],
+    /// the aim pose and pinch state computed by the runtime, if it supports
+    /// `XR_FB_hand_tracking_aim`
+    pub aim: Option<openxr::sys::HandTrackingAimStateFB>,
+}
+
 impl<'a> HandTrackingRef<'a> {
-    pub fn get_left_poses(&self) -> Option<[HandJointLocationEXT;26]> {
+    /// locates the joints of a hand together with their velocities, `None` if the hand
+    /// isn't tracked right now
+    pub fn locate_joints(
+        &self,
+        hand: Hand,
+        motion_range: HandJointsMotionRange,
+    ) -> Result<Option<HandJointsState>, XrError> {
+        //the safe wrapper has no way to chain the motion range info, so this calls
+        //xrLocateHandJointsEXT directly
+        let motion_range_info = openxr::sys::HandJointsMotionRangeInfoEXT {
+            ty: openxr::sys::HandJointsMotionRangeInfoEXT::TYPE,
+            next: ptr::null(),
+            hand_joints_motion_range: motion_range.to_raw(),
+        };
+        let locate_info = openxr::sys::HandJointsLocateInfoEXT {
+            ty: openxr::sys::HandJointsLocateInfoEXT::TYPE,
+            next: match self.tracking.motion_range_supported {
+                true => &motion_range_info as *const _ as *const _,
+                false => ptr::null(),
+            },
+            base_space: self.input.stage.as_raw(),
+            time: self.frame_state.predicted_display_time,
+        };
+        let mut aim = openxr::sys::HandTrackingAimStateFB {
+            ty: openxr::sys::HandTrackingAimStateFB::TYPE,
+            next: ptr::null_mut(),
+            status: openxr::sys::HandTrackingAimFlagsFB::EMPTY,
+            aim_pose: openxr::Posef::IDENTITY,
+            pinch_strength_index: 0.0,
+            pinch_strength_middle: 0.0,
+            pinch_strength_ring: 0.0,
+            pinch_strength_little: 0.0,
+        };
+        let mut velocities = MaybeUninit::<[HandJointVelocityEXT; 26]>::uninit();
+        let mut velocities_info = openxr::sys::HandJointVelocitiesEXT {
+            ty: openxr::sys::HandJointVelocitiesEXT::TYPE,
+            next: match self.tracking.aim_supported {
+                true => &mut aim as *mut _ as *mut _,
+                false => ptr::null_mut(),
+            },
+            joint_count: 26,
+            joint_velocities: velocities.as_mut_ptr() as *mut _,
+        };
+        let mut locations = MaybeUninit::<[HandJointLocationEXT; 26]>::uninit();
+        let mut locations_info = openxr::sys::HandJointLocationsEXT {
+            ty: openxr::sys::HandJointLocationsEXT::TYPE,
+            next: &mut velocities_info as *mut _ as *mut _,
+            is_active: false.into(),
+            joint_count: 26,
+            joint_locations: locations.as_mut_ptr() as *mut _,
+        };
+        let result = unsafe {
+            (self.tracking.ext.locate_hand_joints)(
+                self.tracking.tracker(hand).as_raw(),
+                &locate_info,
+                &mut locations_info,
+            )
+        };
+        if result.into_raw() < 0 {
+            return Err(XrError::runtime("xrLocateHandJointsEXT", result));
+        }
+        if !bool::from(locations_info.is_active) {
+            return Ok(None);
+        }
+        Ok(Some(unsafe {
+            HandJointsState {
+                locations: locations.assume_init(),
+                velocities: velocities.assume_init(),
+                aim: match self.tracking.aim_supported {
+                    true => Some(aim),
+                    false => None,
+                },
+            }
+        }))
+    }
+    pub fn get_poses(&self, hand: Hand) -> Result<Option<[HandJointLocationEXT; 26]>, XrError> {
+        match hand {
+            Hand::Left => self.get_left_poses(),
+            Hand::Right => self.get_right_poses(),
+        }
+    }
+    pub fn get_left_poses(&self) -> Result<Option<[HandJointLocationEXT;26]>, XrError> {
         self.input
             .stage
             .locate_hand_joints(
                 &self.tracking.left_hand,
-                self.frame_state.lock().unwrap().predicted_display_time,
+                self.frame_state.predicted_display_time,
             )
-            .unwrap()
+            .map_err(|err| XrError::runtime("xrLocateHandJointsEXT", err))
             // .map(|joints| {
             //     joints
             //         .into_iter()
@@ -64,14 +278,14 @@ impl<'a> HandTrackingRef<'a> {
             //         .unwrap()
             // })
     }
-    pub fn get_right_poses(&self) -> Option<[HandJointLocationEXT;26]> {
+    pub fn get_right_poses(&self) -> Result<Option<[HandJointLocationEXT;26]>, XrError> {
         self.input
             .stage
             .locate_hand_joints(
                 &self.tracking.right_hand,
-                self.frame_state.lock().unwrap().predicted_display_time,
+                self.frame_state.predicted_display_time,
             )
-            .unwrap()
+            .map_err(|err| XrError::runtime("xrLocateHandJointsEXT", err))
             // .map(|joints| {
             //     joints
             //         .into_iter()
@@ -1,22 +1,233 @@
+use std::collections::{HashMap, HashSet};
 use std::f32::consts::PI;
 
+use bevy::math::Affine3A;
 use bevy::prelude::{
-    info, Color, Component, Entity, Event, EventReader, EventWriter, Gizmos, GlobalTransform, Quat,
-    Query, Transform, Vec3, With, Without,
+    App, Bundle, Color, Component, Entity, Event, EventReader, EventWriter, Gizmos,
+    GlobalTransform, IntoSystemConfigs, IntoSystemSetConfigs, Plugin, Quat, Query, Res, Resource,
+    Startup, SystemSet, Transform, Update, Vec3, With, Without,
 };
+use bevy::render::primitives::Aabb;
 
+use super::actions::attach_action_sets;
+use super::gestures::update_hand_gestures;
+#[cfg(feature = "rapier")]
+use super::grabbable::apply_grabs_to_rapier_bodies;
+use super::grabbable::{insert_grab_states, move_grabbables, update_grab_holders, XrGrabReleased};
+use super::interactor_input::{
+    setup_interactor_actions, update_interactor_activation, update_interactor_inputs,
+    ActivateEntered, ActivateExited, XrInteractorInputConfig,
+};
+use super::oculus_touch::setup_oculus_controller;
+#[cfg(feature = "rapier")]
+use super::sockets::apply_sockets_to_rapier_bodies;
+use super::sockets::{draw_socket_previews, update_sockets, Socketed, Unsocketed, XrSocket};
 use super::trackers::{AimPose, OpenXRTrackingRoot};
 
-#[derive(Component)]
+/// Adds the interaction systems in the [`XrInteractionSet`]s and the [`InteractionEvent`]
+#[derive(Default)]
+pub struct XrInteractionPlugin;
+
+impl Plugin for XrInteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InteractionEvent>()
+            .add_event::<HoverEntered>()
+            .add_event::<HoverExited>()
+            .add_event::<SelectEntered>()
+            .add_event::<SelectExited>()
+            .add_event::<XrGrabReleased>()
+            .add_event::<Socketed>()
+            .add_event::<Unsocketed>()
+            .add_event::<ActivateEntered>()
+            .add_event::<ActivateExited>()
+            .init_resource::<XrInteractionGizmoConfig>()
+            .init_resource::<XrInteractorInputConfig>()
+            .configure_sets(
+                Update,
+                (
+                    XrInteractionSet::Detect,
+                    XrInteractionSet::Resolve,
+                    XrInteractionSet::UpdateStates,
+                    XrInteractionSet::Feedback,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Startup,
+                setup_interactor_actions
+                    .after(setup_oculus_controller)
+                    .before(attach_action_sets),
+            )
+            .add_systems(
+                Update,
+                (
+                    insert_grab_states,
+                    update_interactor_inputs.after(update_hand_gestures),
+                )
+                    .before(XrInteractionSet::Detect),
+            )
+            .add_systems(
+                Update,
+                (interactions, socket_interactions).in_set(XrInteractionSet::Detect),
+            )
+            .add_systems(
+                Update,
+                resolve_interactions.in_set(XrInteractionSet::Resolve),
+            )
+            .add_systems(
+                Update,
+                (update_interactable_states, update_interactor_activation)
+                    .in_set(XrInteractionSet::UpdateStates),
+            )
+            .add_systems(
+                Update,
+                (
+                    draw_interaction_gizmos
+                        .run_if(|config: Res<XrInteractionGizmoConfig>| config.interactions),
+                    (draw_socket_gizmos, draw_socket_previews)
+                        .run_if(|config: Res<XrInteractionGizmoConfig>| config.sockets),
+                    (update_grab_holders, move_grabbables, update_sockets).chain(),
+                )
+                    .in_set(XrInteractionSet::Feedback),
+            );
+        #[cfg(feature = "rapier")]
+        app.add_systems(
+            Update,
+            (
+                apply_grabs_to_rapier_bodies.after(move_grabbables),
+                apply_sockets_to_rapier_bodies
+                    .after(apply_grabs_to_rapier_bodies)
+                    .after(update_sockets),
+            )
+                .in_set(XrInteractionSet::Feedback),
+        );
+    }
+}
+
+/// The steps of the interactions, in the order they run in `Update`.
+///
+/// Systems setting the [`XRInteractorState`] should run before [`XrInteractionSet::Detect`]. The
+/// [`XrInteractorInput`](super::interactor_input::XrInteractorInput) of an interactor does
+/// that from its actions.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum XrInteractionSet {
+    /// interactors look for interactables and send [`InteractionEvent`]s
+    Detect,
+    /// picks one interactable per interactor, updates the [`XRSelection`]s and sends the
+    /// [`HoverEntered`], [`HoverExited`], [`SelectEntered`] and [`SelectExited`] events
+    Resolve,
+    /// applies the interactions to the [`XRInteractableState`]s
+    UpdateStates,
+    /// [`XrGrabbable`](super::grabbable::XrGrabbable)s, gizmos and everything else reacting to
+    /// the new states
+    Feedback,
+}
+
+/// Which interaction gizmos [`XrInteractionPlugin`] draws
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct XrInteractionGizmoConfig {
+    /// interactables, direct and ray interactors
+    pub interactions: bool,
+    pub sockets: bool,
+}
+
+impl Default for XrInteractionGizmoConfig {
+    fn default() -> Self {
+        Self {
+            interactions: true,
+            sockets: true,
+        }
+    }
+}
+
+#[derive(Component, Default)]
 pub struct XRDirectInteractor;
 
-#[derive(Component)]
-pub struct XRRayInteractor;
+/// Casts a ray from the [`AimPose`] of the interactor
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XRRayInteractor {
+    /// length of the ray in meters, measured along the curve
+    pub max_length: f32,
+    pub curve: XrRayCurve,
+}
+
+impl Default for XRRayInteractor {
+    fn default() -> Self {
+        Self {
+            max_length: 10.0,
+            curve: XrRayCurve::Straight,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XrRayCurve {
+    Straight,
+    /// bends down like something thrown along the aim, e.g. for teleporting
+    Projectile {
+        /// how fast it leaves the aim pose in meters per second
+        speed: f32,
+        /// downwards acceleration in meters per second squared
+        gravity: f32,
+    },
+}
+
+// segments of projectile curves, all about the same length
+const PROJECTILE_SEGMENTS: usize = 32;
+
+impl XRRayInteractor {
+    /// The points of the ray from `origin` along `direction`, in the same space as them
+    pub fn path(&self, origin: Vec3, direction: Vec3) -> Vec<Vec3> {
+        let direction = direction.normalize_or_zero();
+        match self.curve {
+            XrRayCurve::Straight => vec![origin, origin + direction * self.max_length],
+            XrRayCurve::Projectile { speed, gravity } => {
+                let step = self.max_length / PROJECTILE_SEGMENTS as f32;
+                let gravity = Vec3::new(0.0, -gravity, 0.0);
+                let mut velocity = direction * speed;
+                let mut points = vec![origin];
+                for _ in 0..PROJECTILE_SEGMENTS {
+                    let speed = velocity.length();
+                    if speed <= f32::EPSILON {
+                        break;
+                    }
+                    //time steps that move about the same distance every segment
+                    let dt = step / speed;
+                    let last = points[points.len() - 1];
+                    points.push(last + velocity * dt + 0.5 * gravity * dt * dt);
+                    velocity += gravity * dt;
+                }
+                points
+            }
+        }
+    }
+}
+
+/// Where the ray of a ray interactor went this frame in world space, it ends at the
+/// [`XrRayHit`] if there is one and is empty without an [`AimPose`]
+#[derive(Component, Clone, Debug, Default)]
+pub struct XrRayPath(pub Vec<Vec3>);
+
+// the start of `path` until `distance` along it
+pub(crate) fn cut_path(path: &[Vec3], distance: f32) -> Vec<Vec3> {
+    let mut points = Vec::new();
+    let mut remaining = distance;
+    for segment in path.windows(2) {
+        points.push(segment[0]);
+        let length = segment[0].distance(segment[1]);
+        if remaining <= length {
+            points.push(segment[0].lerp(segment[1], remaining / length.max(f32::EPSILON)));
+            return points;
+        }
+        remaining -= length;
+    }
+    path.to_vec()
+}
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct XRSocketInteractor;
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct Touched(pub bool);
 
 #[derive(Component, Clone, Copy, PartialEq, PartialOrd, Debug)]
@@ -32,7 +243,7 @@ impl Default for XRInteractableState {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum XRInteractorState {
     Idle,
     Selecting,
@@ -42,10 +253,10 @@ impl Default for XRInteractorState {
         XRInteractorState::Idle
     }
 }
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
 pub enum XRSelection {
     Empty,
-    Full(Entity)
+    Full(Entity),
 }
 impl Default for XRSelection {
     fn default() -> Self {
@@ -53,9 +264,64 @@ impl Default for XRSelection {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct XRInteractable;
 
+/// The interactable an interactor is hovering, there is at most one per interactor
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct XrHoverTarget(pub Option<Entity>);
+
+/// Interactors prefer interactables with a higher priority over closer ones, the default is 0
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct XrInteractionPriority(pub i32);
+
+/// How many interactors can select an interactable at once
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum XrSelectMode {
+    /// only one, interactables without a select mode are exclusive too
+    #[default]
+    Exclusive,
+    /// any number, e.g. for grabbing with both hands
+    Multiple,
+}
+
+#[derive(Bundle, Default)]
+pub struct XrInteractableBundle {
+    pub interactable: XRInteractable,
+    pub state: XRInteractableState,
+    pub touched: Touched,
+}
+
+#[derive(Bundle, Default)]
+pub struct XrDirectInteractorBundle {
+    pub interactor: XRDirectInteractor,
+    pub state: XRInteractorState,
+    pub selection: XRSelection,
+    pub hover: XrHoverTarget,
+}
+
+/// The aim pose is updated for controllers, otherwise it has to be set by whatever points
+/// the ray
+#[derive(Bundle, Default)]
+pub struct XrRayInteractorBundle {
+    pub interactor: XRRayInteractor,
+    pub state: XRInteractorState,
+    pub selection: XRSelection,
+    pub hover: XrHoverTarget,
+    pub aim_pose: AimPose,
+    pub hit: XrRayHit,
+    pub path: XrRayPath,
+}
+
+#[derive(Bundle, Default)]
+pub struct XrSocketInteractorBundle {
+    pub interactor: XRSocketInteractor,
+    pub socket: XrSocket,
+    pub state: XRInteractorState,
+    pub selection: XRSelection,
+    pub hover: XrHoverTarget,
+}
+
 pub fn draw_socket_gizmos(
     mut gizmos: Gizmos,
     interactor_query: Query<(
@@ -79,7 +345,12 @@ pub fn draw_socket_gizmos(
 pub fn draw_interaction_gizmos(
     mut gizmos: Gizmos,
     interactable_query: Query<
-        (&GlobalTransform, &XRInteractableState),
+        (
+            &GlobalTransform,
+            &XRInteractableState,
+            Option<&XrInteractableShape>,
+            Option<&Aabb>,
+        ),
         (With<XRInteractable>, Without<XRDirectInteractor>),
     >,
     interactor_query: Query<
@@ -87,25 +358,48 @@ pub fn draw_interaction_gizmos(
             &GlobalTransform,
             &XRInteractorState,
             Option<&XRDirectInteractor>,
-            Option<&XRRayInteractor>,
-            Option<&AimPose>,
+            Option<&XrRayPath>,
+            Option<&XrRayHit>,
         ),
         Without<XRInteractable>,
     >,
-    tracking_root_query: Query<(&mut Transform, With<OpenXRTrackingRoot>)>,
 ) {
-    let root = tracking_root_query.get_single().unwrap().0;
-    for (global_transform, interactable_state) in interactable_query.iter() {
-        let transform = global_transform.compute_transform();
+    for (global_transform, interactable_state, shape, aabb) in interactable_query.iter() {
         let color = match interactable_state {
             XRInteractableState::Idle => Color::RED,
             XRInteractableState::Hover => Color::YELLOW,
             XRInteractableState::Select => Color::GREEN,
         };
-        gizmos.sphere(transform.translation, transform.rotation, 0.1, color);
+        let shape = shape.copied().unwrap_or_default();
+        let transform = global_transform.compute_transform();
+        //the shapes are hit tested in local space, exact for uniform scale
+        let scale = transform.scale.abs().max_element();
+        match shape.local(aabb) {
+            LocalShape::Sphere { center, radius } => {
+                let center = global_transform.transform_point(center);
+                gizmos.sphere(center, transform.rotation, radius * scale, color);
+            }
+            LocalShape::Box {
+                center,
+                half_extents,
+            } => {
+                let local = Transform::from_translation(center).with_scale(half_extents * 2.0);
+                gizmos.cuboid(global_transform.mul_transform(local), color);
+            }
+            LocalShape::Capsule {
+                half_height,
+                radius,
+            } => {
+                for y in [-half_height, half_height] {
+                    let center = global_transform.transform_point(Vec3::new(0.0, y, 0.0));
+                    gizmos.sphere(center, transform.rotation, radius * scale, color);
+                }
+            }
+        }
     }
 
-    for (interactor_global_transform, interactor_state, direct, ray, aim) in interactor_query.iter()
+    for (interactor_global_transform, interactor_state, direct, ray_path, ray_hit) in
+        interactor_query.iter()
     {
         let transform = interactor_global_transform.compute_transform();
         match direct {
@@ -127,22 +421,15 @@ pub fn draw_interaction_gizmos(
             }
             None => (),
         }
-        match ray {
-            Some(_) => match aim {
-                Some(aim) => {
-                    let color = match interactor_state {
-                        XRInteractorState::Idle => Color::BLUE,
-                        XRInteractorState::Selecting => Color::PURPLE,
-                    };
-                    gizmos.ray(
-                        root.translation + root.rotation.mul_vec3(aim.0.translation),
-                        root.rotation.mul_vec3(aim.0.forward()),
-                        color,
-                    );
-                }
-                None => todo!(),
-            },
-            None => (),
+        if let Some(ray_path) = ray_path {
+            let color = match interactor_state {
+                XRInteractorState::Idle => Color::BLUE,
+                XRInteractorState::Selecting => Color::PURPLE,
+            };
+            gizmos.linestrip(ray_path.0.iter().copied(), color);
+            if let Some((_, hit)) = ray_hit.and_then(|ray_hit| ray_hit.0) {
+                gizmos.circle(hit.point, hit.normal, 0.02, color);
+            }
         }
     }
 }
@@ -152,61 +439,72 @@ pub struct InteractionEvent {
     pub interactor: Entity,
     pub interactable: Entity,
     pub interactable_state: XRInteractableState,
+    /// where the interactable was hit, `None` for interactors that don't compute hits
+    pub hit: Option<XrInteractionHit>,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HoverEntered {
+    pub interactor: Entity,
+    pub interactable: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HoverExited {
+    pub interactor: Entity,
+    pub interactable: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SelectEntered {
+    pub interactor: Entity,
+    pub interactable: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SelectExited {
+    pub interactor: Entity,
+    pub interactable: Entity,
 }
 
+// direct and socket interactors reach this far past their origin
+const INTERACTOR_RADIUS: f32 = 0.04;
+
 pub fn socket_interactions(
     interactable_query: Query<
-        (&GlobalTransform, &mut XRInteractableState, Entity),
-        (With<XRInteractable>, Without<XRSocketInteractor>),
-    >,
-    interactor_query: Query<
         (
             &GlobalTransform,
-            &XRInteractorState,
             Entity,
-            &XRSocketInteractor,
+            Option<&XrInteractableShape>,
+            Option<&Aabb>,
         ),
+        (With<XRInteractable>, Without<XRSocketInteractor>),
+    >,
+    interactor_query: Query<
+        (&GlobalTransform, Entity, &XRSocketInteractor),
         Without<XRInteractable>,
     >,
     mut writer: EventWriter<InteractionEvent>,
 ) {
-    for interactable in interactable_query.iter() {
+    for (xr_interactable_global_transform, interactable_entity, shape, aabb) in
+        interactable_query.iter()
+    {
         //for the interactables
-        for socket in interactor_query.iter() {
-            let interactor_global_transform = socket.0;
-            let xr_interactable_global_transform = interactable.0;
-            let interactor_state = socket.1;
-            //check for sphere overlaps
-            let size = 0.1;
-            if interactor_global_transform
-                .compute_transform()
-                .translation
-                .distance_squared(
-                    xr_interactable_global_transform
-                        .compute_transform()
-                        .translation,
-                )
-                < (size * size) * 2.0
-            {
-                //check for selections first
-                match interactor_state {
-                    XRInteractorState::Idle => {
-                        let event = InteractionEvent {
-                            interactor: socket.2,
-                            interactable: interactable.2,
-                            interactable_state: XRInteractableState::Hover,
-                        };
-                        writer.send(event);
-                    }
-                    XRInteractorState::Selecting => {
-                        let event = InteractionEvent {
-                            interactor: socket.2,
-                            interactable: interactable.2,
-                            interactable_state: XRInteractableState::Select,
-                        };
-                        writer.send(event);
-                    }
-                }
+        for (interactor_global_transform, interactor_entity, _socket) in interactor_query.iter() {
+            //check for shape overlaps
+            let hit = shape.copied().unwrap_or_default().closest_point(
+                xr_interactable_global_transform,
+                aabb,
+                interactor_global_transform.translation(),
+            );
+            if hit.distance < INTERACTOR_RADIUS {
+                //sockets hold grabbables through their XrSocket, they only ever hover
+                writer.send(InteractionEvent {
+                    interactor: interactor_entity,
+                    interactable: interactable_entity,
+                    interactable_state: XRInteractableState::Hover,
+                    hit: Some(hit),
+                });
             }
         }
     }
@@ -214,10 +512,15 @@ pub fn socket_interactions(
 
 pub fn interactions(
     interactable_query: Query<
-        (&GlobalTransform, Entity),
+        (
+            &GlobalTransform,
+            Entity,
+            Option<&XrInteractableShape>,
+            Option<&Aabb>,
+        ),
         (With<XRInteractable>, Without<XRDirectInteractor>),
     >,
-    interactor_query: Query<
+    mut interactor_query: Query<
         (
             &GlobalTransform,
             &XRInteractorState,
@@ -225,156 +528,703 @@ pub fn interactions(
             Option<&XRDirectInteractor>,
             Option<&XRRayInteractor>,
             Option<&AimPose>,
+            Option<&mut XrRayHit>,
+            Option<&mut XrRayPath>,
         ),
         Without<XRInteractable>,
     >,
-    tracking_root_query: Query<(&mut Transform, With<OpenXRTrackingRoot>)>,
+    tracking_root_query: Query<&Transform, With<OpenXRTrackingRoot>>,
     mut writer: EventWriter<InteractionEvent>,
 ) {
-    for (xr_interactable_global_transform, interactable_entity) in interactable_query.iter() {
-        for (interactor_global_transform, interactor_state, interactor_entity, direct, ray, aim) in
-            interactor_query.iter()
+    let root = tracking_root_query.get_single().ok();
+    for (
+        interactor_global_transform,
+        interactor_state,
+        interactor_entity,
+        direct,
+        ray,
+        aim,
+        mut ray_hit,
+        mut ray_path,
+    ) in interactor_query.iter_mut()
+    {
+        //check for selections first
+        let interactable_state = match interactor_state {
+            XRInteractorState::Idle => XRInteractableState::Hover,
+            XRInteractorState::Selecting => XRInteractableState::Select,
+        };
+        //rays without an aim pose or tracking root don't hit anything
+        let path = match (ray, aim, root) {
+            (Some(ray), Some(aim), Some(root)) => {
+                //I hate this but the aim pose needs the root for now
+                let ray_origin = root.translation + root.rotation.mul_vec3(aim.0.translation);
+                let ray_dir = root.rotation.mul_vec3(aim.0.forward());
+                ray.path(ray_origin, ray_dir)
+            }
+            _ => Vec::new(),
+        };
+        let mut closest_hit: Option<(Entity, XrInteractionHit)> = None;
+        for (xr_interactable_global_transform, interactable_entity, shape, aabb) in
+            interactable_query.iter()
         {
-            match direct {
-                Some(_) => {
-                    //check for sphere overlaps
-                    let size = 0.1;
-                    if interactor_global_transform
-                        .compute_transform()
-                        .translation
-                        .distance_squared(
-                            xr_interactable_global_transform
-                                .compute_transform()
-                                .translation,
-                        )
-                        < (size * size) * 2.0
-                    {
-                        //check for selections first
-                        match interactor_state {
-                            XRInteractorState::Idle => {
-                                let event = InteractionEvent {
-                                    interactor: interactor_entity,
-                                    interactable: interactable_entity,
-                                    interactable_state: XRInteractableState::Hover,
-                                };
-                                writer.send(event);
-                            }
-                            XRInteractorState::Selecting => {
-                                let event = InteractionEvent {
-                                    interactor: interactor_entity,
-                                    interactable: interactable_entity,
-                                    interactable_state: XRInteractableState::Select,
-                                };
-                                writer.send(event);
-                            }
-                        }
-                    }
+            let shape = shape.copied().unwrap_or_default();
+            if direct.is_some() {
+                //check for shape overlaps
+                let hit = shape.closest_point(
+                    xr_interactable_global_transform,
+                    aabb,
+                    interactor_global_transform.translation(),
+                );
+                if hit.distance < INTERACTOR_RADIUS {
+                    writer.send(InteractionEvent {
+                        interactor: interactor_entity,
+                        interactable: interactable_entity,
+                        interactable_state,
+                        hit: Some(hit),
+                    });
                 }
-                None => (),
             }
-            match ray {
-                Some(_) => {
-                    //check for ray-sphere intersection
-                    let sphere_transform = xr_interactable_global_transform.compute_transform();
-                    let center = sphere_transform.translation;
-                    let radius: f32 = 0.1;
-                    //I hate this but the aim pose needs the root for now
-                    let root = tracking_root_query.get_single().unwrap().0;
-                    match aim {
-                        Some(aim) => {
-                            let ray_origin =
-                                root.translation + root.rotation.mul_vec3(aim.0.translation);
-                            let ray_dir = root.rotation.mul_vec3(aim.0.forward());
-
-                            if ray_sphere_intersection(
-                                center,
-                                radius,
-                                ray_origin,
-                                ray_dir.normalize_or_zero(),
-                            ) {
-                                //check for selections first
-                                match interactor_state {
-                                    XRInteractorState::Idle => {
-                                        let event = InteractionEvent {
-                                            interactor: interactor_entity,
-                                            interactable: interactable_entity,
-                                            interactable_state: XRInteractableState::Hover,
-                                        };
-                                        writer.send(event);
-                                    }
-                                    XRInteractorState::Selecting => {
-                                        let event = InteractionEvent {
-                                            interactor: interactor_entity,
-                                            interactable: interactable_entity,
-                                            interactable_state: XRInteractableState::Select,
-                                        };
-                                        writer.send(event);
-                                    }
-                                }
-                            }
-                        }
-                        None => info!("no aim pose"),
-                    }
+            //check for ray-shape intersection
+            if let Some(hit) = shape.raycast_path(xr_interactable_global_transform, aabb, &path) {
+                let closer =
+                    closest_hit.map_or(true, |(_, closest)| hit.distance < closest.distance);
+                if closer {
+                    closest_hit = Some((interactable_entity, hit));
                 }
-                None => (),
+                writer.send(InteractionEvent {
+                    interactor: interactor_entity,
+                    interactable: interactable_entity,
+                    interactable_state,
+                    hit: Some(hit),
+                });
+            }
+        }
+        if let Some(ray_hit) = ray_hit.as_mut() {
+            ray_hit.0 = closest_hit;
+        }
+        if let Some(ray_path) = ray_path.as_mut() {
+            ray_path.0 = match closest_hit {
+                Some((_, hit)) => cut_path(&path, hit.distance),
+                None => path,
             }
         }
     }
 }
 
-pub fn update_interactable_states(
+/// Picks the best interactable for every interactor out of this frame's [`InteractionEvent`]s,
+/// the one with the highest [`XrInteractionPriority`] and then the closest hit.
+///
+/// Interactors keep their selection until they stop selecting, so rays can move off of what
+/// they hold. Only interactors with an [`XRSelection`] and an [`XrHoverTarget`] take part.
+pub fn resolve_interactions(
     mut events: EventReader<InteractionEvent>,
+    interactable_query: Query<
+        (Option<&XrInteractionPriority>, Option<&XrSelectMode>),
+        With<XRInteractable>,
+    >,
+    mut interactor_query: Query<(
+        Entity,
+        &XRInteractorState,
+        &mut XRSelection,
+        &mut XrHoverTarget,
+        Option<&XRSocketInteractor>,
+    )>,
+    mut hover_entered: EventWriter<HoverEntered>,
+    mut hover_exited: EventWriter<HoverExited>,
+    mut select_entered: EventWriter<SelectEntered>,
+    mut select_exited: EventWriter<SelectExited>,
+) {
+    //the best interactable of every interactor with its priority and distance
+    let mut best: HashMap<Entity, (Entity, XrInteractionPriority, f32)> = HashMap::new();
+    for event in events.read() {
+        let priority = match interactable_query.get(event.interactable) {
+            Ok((priority, _)) => priority.copied().unwrap_or_default(),
+            Err(_) => continue,
+        };
+        let distance = event.hit.map_or(0.0, |hit| hit.distance);
+        let better = match best.get(&event.interactor) {
+            Some((_, best_priority, best_distance)) => {
+                priority > *best_priority
+                    || (priority == *best_priority && distance < *best_distance)
+            }
+            None => true,
+        };
+        if better {
+            best.insert(event.interactor, (event.interactable, priority, distance));
+        }
+    }
+
+    //release first so whatever was let go of can be selected again this frame
+    let mut selected = HashSet::new();
+    for (interactor, state, mut selection, _, socket) in interactor_query.iter_mut() {
+        match (*selection, socket) {
+            //sockets set their own selection and give it up to anything grabbing from them
+            (_, Some(_)) => (),
+            (XRSelection::Full(interactable), None) => {
+                let exists = interactable_query.contains(interactable);
+                if *state == XRInteractorState::Idle || !exists {
+                    *selection = XRSelection::Empty;
+                    select_exited.send(SelectExited {
+                        interactor,
+                        interactable,
+                    });
+                } else {
+                    selected.insert(interactable);
+                }
+            }
+            (XRSelection::Empty, None) => (),
+        }
+    }
+
+    for (interactor, state, mut selection, mut hover, socket) in interactor_query.iter_mut() {
+        let target = best
+            .get(&interactor)
+            .map(|(interactable, _, _)| *interactable);
+        if hover.0 != target {
+            if let Some(interactable) = hover.0 {
+                hover_exited.send(HoverExited {
+                    interactor,
+                    interactable,
+                });
+            }
+            if let Some(interactable) = target {
+                hover_entered.send(HoverEntered {
+                    interactor,
+                    interactable,
+                });
+            }
+            hover.0 = target;
+        }
+
+        let interactable = match (*state, *selection, target, socket) {
+            (XRInteractorState::Selecting, XRSelection::Empty, Some(interactable), None) => {
+                interactable
+            }
+            _ => continue,
+        };
+        let exclusive = match interactable_query.get(interactable) {
+            Ok((_, mode)) => mode.copied().unwrap_or_default() == XrSelectMode::Exclusive,
+            Err(_) => continue,
+        };
+        if exclusive && selected.contains(&interactable) {
+            continue;
+        }
+        selected.insert(interactable);
+        *selection = XRSelection::Full(interactable);
+        select_entered.send(SelectEntered {
+            interactor,
+            interactable,
+        });
+    }
+}
+
+/// Sets the [`XRInteractableState`]s from what the interactors hover and select
+pub fn update_interactable_states(
+    interactor_query: Query<(&XRSelection, &XrHoverTarget)>,
     mut interactable_query: Query<
         (Entity, &mut XRInteractableState, &mut Touched),
         With<XRInteractable>,
     >,
 ) {
-    //i very much dislike this
-    for (_entity, _state, mut touched) in interactable_query.iter_mut() {
-        *touched = Touched(false);
+    let mut states: HashMap<Entity, XRInteractableState> = HashMap::new();
+    for (selection, hover) in interactor_query.iter() {
+        if let Some(interactable) = hover.0 {
+            states
+                .entry(interactable)
+                .or_insert(XRInteractableState::Hover);
+        }
+        if let XRSelection::Full(interactable) = selection {
+            states.insert(*interactable, XRInteractableState::Select);
+        }
     }
-    for event in events.read() {
-        //lets change the state
-        match interactable_query.get_mut(event.interactable) {
-            Ok((_entity, mut entity_state, mut touched)) => {
-                //since we have an event we were touched this frame, i hate this name
-                *touched = Touched(true);
-                if event.interactable_state > *entity_state {
-                    // info!(
-                    //     "event.state: {:?}, interactable.state: {:?}",
-                    //     event.interactable_state, entity_state
-                    // );
-                    // info!("event has a higher state");
+    for (entity, mut state, mut touched) in interactable_query.iter_mut() {
+        let new_state = states
+            .get(&entity)
+            .copied()
+            .unwrap_or(XRInteractableState::Idle);
+        if *state != new_state {
+            *state = new_state;
+        }
+        //touched means something is hovering or selecting it
+        let is_touched = new_state != XRInteractableState::Idle;
+        if touched.0 != is_touched {
+            *touched = Touched(is_touched);
+        }
+    }
+}
+
+/// The shape interactors hit, in the local space of the interactable.
+///
+/// Interactables without one are spheres with a radius of 0.1.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum XrInteractableShape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vec3,
+    },
+    /// along the local y axis, `half_height` doesn't include the caps
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    /// the [`Aabb`] bevy computes for the mesh of the interactable
+    MeshAabb,
+}
+
+impl Default for XrInteractableShape {
+    fn default() -> Self {
+        XrInteractableShape::Sphere { radius: 0.1 }
+    }
+}
+
+/// Where an interactor hit an [`XrInteractableShape`], in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XrInteractionHit {
+    pub point: Vec3,
+    /// the surface normal at `point`
+    pub normal: Vec3,
+    /// from the ray origin for ray casts, to the surface for overlaps where it's negative
+    /// inside of the shape
+    pub distance: f32,
+}
+
+/// The closest interactable a ray interactor hit this frame
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct XrRayHit(pub Option<(Entity, XrInteractionHit)>);
+
+// the shapes with the aabb resolved, all of them are centered on the local origin except for
+// the aabb
+enum LocalShape {
+    Sphere { center: Vec3, radius: f32 },
+    Box { center: Vec3, half_extents: Vec3 },
+    Capsule { half_height: f32, radius: f32 },
+}
+
+impl XrInteractableShape {
+    fn local(&self, aabb: Option<&Aabb>) -> LocalShape {
+        match *self {
+            XrInteractableShape::Sphere { radius } => LocalShape::Sphere {
+                center: Vec3::ZERO,
+                radius,
+            },
+            XrInteractableShape::Box { half_extents } => LocalShape::Box {
+                center: Vec3::ZERO,
+                half_extents,
+            },
+            XrInteractableShape::Capsule {
+                half_height,
+                radius,
+            } => LocalShape::Capsule {
+                half_height,
+                radius,
+            },
+            XrInteractableShape::MeshAabb => match aabb {
+                Some(aabb) => LocalShape::Box {
+                    center: aabb.center.into(),
+                    half_extents: aabb.half_extents.into(),
+                },
+                None => XrInteractableShape::default().local(None),
+            },
+        }
+    }
+
+    /// Casts a ray against the shape placed at `transform`, `aabb` is only used by
+    /// [`XrInteractableShape::MeshAabb`]
+    pub fn raycast(
+        &self,
+        transform: &GlobalTransform,
+        aabb: Option<&Aabb>,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<XrInteractionHit> {
+        let affine = transform.affine();
+        let inverse = affine.inverse();
+        //the direction isn't normalized in local space, so t stays the same in both spaces
+        let local_origin = inverse.transform_point3(origin);
+        let local_direction = inverse.transform_vector3(direction);
+        let (t, local_normal) = match self.local(aabb) {
+            LocalShape::Sphere { center, radius } => {
+                ray_sphere(local_origin - center, local_direction, radius)?
+            }
+            LocalShape::Box {
+                center,
+                half_extents,
+            } => ray_box(local_origin - center, local_direction, half_extents)?,
+            LocalShape::Capsule {
+                half_height,
+                radius,
+            } => ray_capsule(local_origin, local_direction, half_height, radius)?,
+        };
+        let point = origin + direction * t;
+        Some(XrInteractionHit {
+            point,
+            normal: normal_to_world(&inverse, local_normal),
+            distance: origin.distance(point),
+        })
+    }
+
+    /// Casts a ray along the segments of `path`, the distance of the hit is measured along it
+    pub fn raycast_path(
+        &self,
+        transform: &GlobalTransform,
+        aabb: Option<&Aabb>,
+        path: &[Vec3],
+    ) -> Option<XrInteractionHit> {
+        let mut start = 0.0;
+        for segment in path.windows(2) {
+            let offset = segment[1] - segment[0];
+            let length = offset.length();
+            if length <= f32::EPSILON {
+                continue;
+            }
+            match self.raycast(transform, aabb, segment[0], offset / length) {
+                Some(hit) if hit.distance <= length => {
+                    return Some(XrInteractionHit {
+                        distance: start + hit.distance,
+                        ..hit
+                    });
                 }
-                *entity_state = event.interactable_state;
+                _ => (),
+            }
+            start += length;
+        }
+        None
+    }
+
+    /// The point on the surface of the shape placed at `transform` closest to `point`
+    pub fn closest_point(
+        &self,
+        transform: &GlobalTransform,
+        aabb: Option<&Aabb>,
+        point: Vec3,
+    ) -> XrInteractionHit {
+        let affine = transform.affine();
+        let inverse = affine.inverse();
+        let local_point = inverse.transform_point3(point);
+        let (surface, local_normal, inside) = match self.local(aabb) {
+            LocalShape::Sphere { center, radius } => closest_on_sphere(local_point, center, radius),
+            LocalShape::Box {
+                center,
+                half_extents,
+            } => {
+                let (surface, normal, inside) = closest_on_box(local_point - center, half_extents);
+                (surface + center, normal, inside)
+            }
+            LocalShape::Capsule {
+                half_height,
+                radius,
+            } => {
+                let center = Vec3::new(0.0, local_point.y.clamp(-half_height, half_height), 0.0);
+                closest_on_sphere(local_point, center, radius)
             }
-            Err(_) => {}
+        };
+        let surface = affine.transform_point3(surface);
+        let distance = point.distance(surface);
+        XrInteractionHit {
+            point: surface,
+            normal: normal_to_world(&inverse, local_normal),
+            distance: match inside {
+                true => -distance,
+                false => distance,
+            },
         }
     }
-    //lets go through all the untouched interactables and set them to idle
-    for (_entity, mut state, touched) in interactable_query.iter_mut() {
-        if !touched.0 {
-            *state = XRInteractableState::Idle;
+}
+
+fn normal_to_world(inverse: &Affine3A, normal: Vec3) -> Vec3 {
+    //normals need the inverse transpose to stay perpendicular with non uniform scale
+    inverse
+        .matrix3
+        .transpose()
+        .mul_vec3(normal)
+        .normalize_or_zero()
+}
+
+// the nearest t along the ray that isn't behind the origin, with the local normal
+fn ray_sphere(origin: Vec3, direction: Vec3, radius: f32) -> Option<(f32, Vec3)> {
+    let a = direction.dot(direction);
+    let b = origin.dot(direction);
+    let c = origin.dot(origin) - radius * radius;
+    let discriminant = b * b - a * c;
+    if a <= f32::EPSILON || discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let t0 = (-b - root) / a;
+    let t1 = (-b + root) / a;
+    let t = match (t0 >= 0.0, t1 >= 0.0) {
+        (true, _) => t0,
+        (false, true) => t1,
+        (false, false) => return None,
+    };
+    Some((t, (origin + direction * t) / radius))
+}
+
+fn ray_box(origin: Vec3, direction: Vec3, half_extents: Vec3) -> Option<(f32, Vec3)> {
+    let mut t_min = f32::NEG_INFINITY;
+    let mut t_max = f32::INFINITY;
+    let mut min_normal = Vec3::ZERO;
+    let mut max_normal = Vec3::ZERO;
+    for axis in 0..3 {
+        if direction[axis].abs() <= f32::EPSILON {
+            //parallel to the slab, either always inside of it or never
+            if origin[axis].abs() > half_extents[axis] {
+                return None;
+            }
+            continue;
+        }
+        let sign = direction[axis].signum();
+        let near = (-sign * half_extents[axis] - origin[axis]) / direction[axis];
+        let far = (sign * half_extents[axis] - origin[axis]) / direction[axis];
+        if near > t_min {
+            t_min = near;
+            min_normal = Vec3::ZERO;
+            min_normal[axis] = -sign;
+        }
+        if far < t_max {
+            t_max = far;
+            max_normal = Vec3::ZERO;
+            max_normal[axis] = sign;
+        }
+    }
+    if t_max < t_min || t_max < 0.0 {
+        return None;
+    }
+    match t_min >= 0.0 {
+        true => Some((t_min, min_normal)),
+        //the origin is inside of the box
+        false => Some((t_max, max_normal)),
+    }
+}
+
+fn ray_capsule(
+    origin: Vec3,
+    direction: Vec3,
+    half_height: f32,
+    radius: f32,
+) -> Option<(f32, Vec3)> {
+    let cap_offset = Vec3::new(0.0, half_height, 0.0);
+    let mut closest = [cap_offset, -cap_offset]
+        .into_iter()
+        .filter_map(|center| ray_sphere(origin - center, direction, radius))
+        .reduce(|a, b| if a.0 < b.0 { a } else { b });
+    //the cylinder between the caps, only the xz plane matters for it
+    let flat_origin = Vec3::new(origin.x, 0.0, origin.z);
+    let flat_direction = Vec3::new(direction.x, 0.0, direction.z);
+    match ray_sphere(flat_origin, flat_direction, radius) {
+        Some((t, normal)) if (origin.y + direction.y * t).abs() <= half_height => {
+            if closest.map_or(true, |(closest_t, _)| t < closest_t) {
+                closest = Some((t, normal));
+            }
         }
+        _ => (),
     }
+    closest
 }
 
-fn ray_sphere_intersection(center: Vec3, radius: f32, ray_origin: Vec3, ray_dir: Vec3) -> bool {
-    let l = center - ray_origin;
-    let adj = l.dot(ray_dir);
-    let d2 = l.dot(l) - (adj * adj);
-    let radius2 = radius * radius;
-    if d2 > radius2 {
-        return false;
+// the closest surface point, its normal and whether the point is inside of the sphere
+fn closest_on_sphere(point: Vec3, center: Vec3, radius: f32) -> (Vec3, Vec3, bool) {
+    let offset = point - center;
+    let normal = offset.try_normalize().unwrap_or(Vec3::Y);
+    (center + normal * radius, normal, offset.length() < radius)
+}
+
+fn closest_on_box(point: Vec3, half_extents: Vec3) -> (Vec3, Vec3, bool) {
+    let clamped = point.clamp(-half_extents, half_extents);
+    if clamped != point {
+        return (clamped, (point - clamped).normalize_or_zero(), false);
     }
-    let thc = (radius2 - d2).sqrt();
-    let t0 = adj - thc;
-    let t1 = adj + thc;
+    //inside, push the point out of the nearest face
+    let distances = half_extents - point.abs();
+    let axis = match (distances.x <= distances.y, distances.x <= distances.z) {
+        (true, true) => 0,
+        _ if distances.y <= distances.z => 1,
+        _ => 2,
+    };
+    let sign = match point[axis] < 0.0 {
+        true => -1.0,
+        false => 1.0,
+    };
+    let mut surface = point;
+    surface[axis] = sign * half_extents[axis];
+    let mut normal = Vec3::ZERO;
+    normal[axis] = sign;
+    (surface, normal, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if t0 < 0.0 && t1 < 0.0 {
-        return false;
+    fn assert_hit(hit: Option<(f32, Vec3)>, t: f32, normal: Vec3) {
+        let (hit_t, hit_normal) = hit.expect("expected a hit");
+        assert!((hit_t - t).abs() < 1e-5, "t {} != {}", hit_t, t);
+        assert!(
+            hit_normal.abs_diff_eq(normal, 1e-5),
+            "normal {} != {}",
+            hit_normal,
+            normal
+        );
     }
 
-    // let distance = if t0 < t1 { t0 } else { t1 };
-    return true;
+    #[test]
+    fn ray_sphere_from_outside() {
+        let hit = ray_sphere(Vec3::new(0.0, 0.0, -2.0), Vec3::Z, 0.5);
+        assert_hit(hit, 1.5, Vec3::NEG_Z);
+    }
+
+    #[test]
+    fn ray_sphere_from_inside_hits_the_far_side() {
+        let hit = ray_sphere(Vec3::ZERO, Vec3::X, 0.5);
+        assert_hit(hit, 0.5, Vec3::X);
+    }
+
+    #[test]
+    fn ray_sphere_behind_the_origin() {
+        assert_eq!(ray_sphere(Vec3::new(0.0, 0.0, 2.0), Vec3::Z, 0.5), None);
+    }
+
+    #[test]
+    fn ray_box_from_outside() {
+        let hit = ray_box(Vec3::new(-2.0, 0.0, 0.0), Vec3::X, Vec3::ONE);
+        assert_hit(hit, 1.0, Vec3::NEG_X);
+    }
+
+    #[test]
+    fn ray_box_from_inside_hits_the_far_side() {
+        let hit = ray_box(Vec3::new(0.5, 0.0, 0.0), Vec3::X, Vec3::ONE);
+        assert_hit(hit, 0.5, Vec3::X);
+    }
+
+    #[test]
+    fn ray_box_parallel_to_a_slab() {
+        let inside = ray_box(Vec3::new(-2.0, 0.5, 0.0), Vec3::X, Vec3::ONE);
+        assert_hit(inside, 1.0, Vec3::NEG_X);
+        let outside = ray_box(Vec3::new(-2.0, 1.5, 0.0), Vec3::X, Vec3::ONE);
+        assert_eq!(outside, None);
+    }
+
+    #[test]
+    fn ray_capsule_from_the_side() {
+        let hit = ray_capsule(Vec3::new(-2.0, 0.5, 0.0), Vec3::X, 1.0, 0.5);
+        assert_hit(hit, 1.5, Vec3::NEG_X);
+    }
+
+    #[test]
+    fn ray_capsule_vertical() {
+        let center = ray_capsule(Vec3::new(0.0, 3.0, 0.0), Vec3::NEG_Y, 1.0, 0.5);
+        assert_hit(center, 1.5, Vec3::Y);
+        //hits the cap off center at y = 1 + sqrt(0.5² - 0.3²)
+        let off_center = ray_capsule(Vec3::new(0.3, 3.0, 0.0), Vec3::NEG_Y, 1.0, 0.5);
+        assert_hit(off_center, 1.6, Vec3::new(0.6, 0.8, 0.0));
+        let outside = ray_capsule(Vec3::new(0.6, 3.0, 0.0), Vec3::NEG_Y, 1.0, 0.5);
+        assert_eq!(outside, None);
+    }
+
+    #[test]
+    fn closest_on_box_inside_pushes_out_of_the_nearest_face() {
+        let (surface, normal, inside) = closest_on_box(Vec3::new(0.8, 0.1, -0.2), Vec3::ONE);
+        assert!(surface.abs_diff_eq(Vec3::new(1.0, 0.1, -0.2), 1e-5));
+        assert_eq!(normal, Vec3::X);
+        assert!(inside);
+        let (surface, normal, inside) = closest_on_box(Vec3::new(0.1, -0.9, 0.0), Vec3::ONE);
+        assert!(surface.abs_diff_eq(Vec3::new(0.1, -1.0, 0.0), 1e-5));
+        assert_eq!(normal, Vec3::NEG_Y);
+        assert!(inside);
+    }
+
+    #[test]
+    fn closest_on_box_outside() {
+        let (surface, normal, inside) = closest_on_box(Vec3::new(2.0, 0.5, 0.0), Vec3::ONE);
+        assert!(surface.abs_diff_eq(Vec3::new(1.0, 0.5, 0.0), 1e-5));
+        assert_eq!(normal, Vec3::X);
+        assert!(!inside);
+    }
+
+    #[test]
+    fn closest_on_sphere_inside_and_outside() {
+        let (surface, normal, inside) =
+            closest_on_sphere(Vec3::new(0.0, 0.2, 0.0), Vec3::ZERO, 0.5);
+        assert!(surface.abs_diff_eq(Vec3::new(0.0, 0.5, 0.0), 1e-5));
+        assert_eq!(normal, Vec3::Y);
+        assert!(inside);
+        let (_, _, inside) = closest_on_sphere(Vec3::new(0.0, 2.0, 0.0), Vec3::ZERO, 0.5);
+        assert!(!inside);
+    }
+
+    #[test]
+    fn closest_point_distance_is_negative_inside() {
+        let shape = XrInteractableShape::Box {
+            half_extents: Vec3::ONE,
+        };
+        let transform = GlobalTransform::from(Transform::from_xyz(0.0, 0.0, 5.0));
+        let inside = shape.closest_point(&transform, None, Vec3::new(0.8, 0.0, 5.0));
+        assert!((inside.distance + 0.2).abs() < 1e-5);
+        assert!(inside.normal.abs_diff_eq(Vec3::X, 1e-5));
+        let outside = shape.closest_point(&transform, None, Vec3::new(3.0, 0.0, 5.0));
+        assert!((outside.distance - 2.0).abs() < 1e-5);
+        assert!(outside.normal.abs_diff_eq(Vec3::X, 1e-5));
+    }
+
+    #[test]
+    fn raycast_through_a_scaled_shape() {
+        let shape = XrInteractableShape::Box {
+            half_extents: Vec3::ONE,
+        };
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(0.0, 0.0, 5.0).with_scale(Vec3::new(1.0, 1.0, 2.0)),
+        );
+        let hit = shape
+            .raycast(&transform, None, Vec3::ZERO, Vec3::Z)
+            .expect("expected a hit");
+        assert!((hit.distance - 3.0).abs() < 1e-5);
+        assert!(hit.point.abs_diff_eq(Vec3::new(0.0, 0.0, 3.0), 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec3::NEG_Z, 1e-5));
+    }
+
+    #[test]
+    fn cut_path_along_the_segments() {
+        let path = [Vec3::ZERO, Vec3::X, Vec3::new(1.0, 2.0, 0.0)];
+        assert_eq!(
+            cut_path(&path, 2.0),
+            vec![Vec3::ZERO, Vec3::X, Vec3::new(1.0, 1.0, 0.0)]
+        );
+        assert_eq!(
+            cut_path(&path, 0.5),
+            vec![Vec3::ZERO, Vec3::new(0.5, 0.0, 0.0)]
+        );
+        assert_eq!(cut_path(&path, 10.0), path.to_vec());
+    }
+
+    #[test]
+    fn straight_path() {
+        let ray = XRRayInteractor {
+            max_length: 2.0,
+            curve: XrRayCurve::Straight,
+        };
+        let path = ray.path(Vec3::ZERO, Vec3::new(0.0, 0.0, -3.0));
+        assert_eq!(path, vec![Vec3::ZERO, Vec3::new(0.0, 0.0, -2.0)]);
+    }
+
+    #[test]
+    fn projectile_path_falls_and_keeps_about_its_length() {
+        let ray = XRRayInteractor {
+            max_length: 10.0,
+            curve: XrRayCurve::Projectile {
+                speed: 5.0,
+                gravity: 9.81,
+            },
+        };
+        let path = ray.path(Vec3::ZERO, Vec3::X);
+        assert_eq!(path.len(), PROJECTILE_SEGMENTS + 1);
+        assert_eq!(path[0], Vec3::ZERO);
+        assert!(path[PROJECTILE_SEGMENTS].y < 0.0);
+        let length: f32 = path
+            .windows(2)
+            .map(|segment| segment[0].distance(segment[1]))
+            .sum();
+        assert!((length - 10.0).abs() < 0.5, "length {}", length);
+    }
 }
@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use openxr as xr;
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::{
+    actions::XrSuggestedBindings,
+    gestures::{GrabStrength, PinchStrength},
+    hand::OpenXRHand,
+    hand_emulation::{HTC_VIVE_PROFILE, VALVE_INDEX_PROFILE},
+    interactions::{XRInteractorState, XRSelection},
+    oculus_touch::{init_subaction_path, subaction_path, ActionSets, OCULUS_TOUCH_PROFILE},
+    Hand,
+};
+
+/// name of the default select action, bound to the grip
+pub const SELECT_ACTION: &str = "interactor_select";
+/// name of the default activate action, bound to the trigger
+pub const ACTIVATE_ACTION: &str = "interactor_activate";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrInteractorActionKind {
+    /// an analog value from 0 to 1, e.g. `input/trigger/value`
+    Value,
+    /// a button, e.g. `input/a/click`
+    Click,
+}
+
+/// One action [`XrInteractorInput`]s can read through [`XrInputSource::Action`]
+#[derive(Clone, Debug)]
+pub struct XrInteractorAction {
+    /// name of the created action, has to be unique and a valid OpenXR action name
+    pub name: String,
+    pub kind: XrInteractorActionKind,
+    /// interaction profile and input path, relative to the hand unless it starts with `/user/`
+    pub bindings: Vec<(String, String)>,
+}
+
+impl XrInteractorAction {
+    pub fn new(name: impl Into<String>, kind: XrInteractorActionKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            bindings: Vec::new(),
+        }
+    }
+
+    pub fn with_binding(mut self, profile: &str, path: &str) -> Self {
+        self.bindings.push((profile.to_string(), path.to_string()));
+        self
+    }
+}
+
+/// The actions created for the [`XrInteractorInput`]s.
+///
+/// They are created at startup, so changes after that don't do anything.
+#[derive(Resource, Clone, Debug)]
+pub struct XrInteractorInputConfig {
+    pub actions: Vec<XrInteractorAction>,
+}
+
+impl Default for XrInteractorInputConfig {
+    fn default() -> Self {
+        use XrInteractorActionKind::*;
+        Self {
+            actions: vec![
+                XrInteractorAction::new(SELECT_ACTION, Value)
+                    .with_binding(OCULUS_TOUCH_PROFILE, "input/squeeze/value")
+                    .with_binding(VALVE_INDEX_PROFILE, "input/squeeze/value")
+                    .with_binding(HTC_VIVE_PROFILE, "input/squeeze/click"),
+                XrInteractorAction::new(ACTIVATE_ACTION, Value)
+                    .with_binding(OCULUS_TOUCH_PROFILE, "input/trigger/value")
+                    .with_binding(VALVE_INDEX_PROFILE, "input/trigger/value")
+                    .with_binding(HTC_VIVE_PROFILE, "input/trigger/value"),
+            ],
+        }
+    }
+}
+
+enum InteractorAction {
+    Value(xr::Action<f32>),
+    Click(xr::Action<bool>),
+}
+
+/// The actions created for [`XrInteractorInputConfig::actions`] by name
+#[derive(Resource)]
+pub struct XrInteractorActions {
+    actions: HashMap<String, InteractorAction>,
+}
+
+impl XrInteractorActions {
+    /// how far the action is pressed on `hand`, from 0 to 1
+    pub fn value(&self, session: &xr::Session<xr::AnyGraphics>, name: &str, hand: Hand) -> f32 {
+        match self.actions.get(name) {
+            Some(InteractorAction::Value(action)) => action
+                .state(session, subaction_path(hand))
+                .map_or(0.0, |state| match state.is_active {
+                    true => state.current_state,
+                    false => 0.0,
+                }),
+            Some(InteractorAction::Click(action)) => action
+                .state(session, subaction_path(hand))
+                .map_or(0.0, |state| match state.is_active && state.current_state {
+                    true => 1.0,
+                    false => 0.0,
+                }),
+            None => 0.0,
+        }
+    }
+}
+
+/// Where the value of an [`XrInteractorButton`] comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum XrInputSource {
+    /// an action of the [`XrInteractorInputConfig`] by name
+    Action(String),
+    /// the [`PinchStrength`] of the hand, needs the
+    /// [`HandGesturePlugin`](super::gestures::HandGesturePlugin)
+    Pinch,
+    /// the [`GrabStrength`] of the hand, needs the
+    /// [`HandGesturePlugin`](super::gestures::HandGesturePlugin)
+    Grab,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XrInputBehavior {
+    /// active while the input is held down
+    #[default]
+    Hold,
+    /// every press switches between active and inactive
+    Toggle,
+}
+
+/// One input of an [`XrInteractorInput`], e.g. select.
+///
+/// The strongest of the sources counts, so a controller action and a hand gesture can drive the
+/// same interactor and whichever is in use wins.
+#[derive(Clone, Debug)]
+pub struct XrInteractorButton {
+    pub sources: Vec<XrInputSource>,
+    pub behavior: XrInputBehavior,
+    /// the input is pressed once its value rises above this
+    pub press_threshold: f32,
+    /// a press ends once the value falls below this, lower than `press_threshold` so analog
+    /// inputs don't flicker on the edge
+    pub release_threshold: f32,
+    pressed: bool,
+    active: bool,
+}
+
+impl XrInteractorButton {
+    pub fn new(sources: impl IntoIterator<Item = XrInputSource>) -> Self {
+        Self {
+            sources: sources.into_iter().collect(),
+            behavior: XrInputBehavior::Hold,
+            press_threshold: 0.75,
+            release_threshold: 0.6,
+            pressed: false,
+            active: false,
+        }
+    }
+
+    /// a button that is never pressed
+    pub fn none() -> Self {
+        Self::new([])
+    }
+
+    pub fn with_behavior(mut self, behavior: XrInputBehavior) -> Self {
+        self.behavior = behavior;
+        self
+    }
+
+    pub fn with_thresholds(mut self, press_threshold: f32, release_threshold: f32) -> Self {
+        self.press_threshold = press_threshold;
+        self.release_threshold = release_threshold;
+        self
+    }
+
+    /// whether the input is held down right now
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// whether the input is active, which is the same as pressed unless it toggles
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn update(&mut self, value: f32) {
+        let pressed = match self.pressed {
+            true => value >= self.release_threshold,
+            false => value >= self.press_threshold,
+        };
+        self.active = match self.behavior {
+            XrInputBehavior::Hold => pressed,
+            XrInputBehavior::Toggle if pressed && !self.pressed => !self.active,
+            XrInputBehavior::Toggle => self.active,
+        };
+        self.pressed = pressed;
+    }
+}
+
+/// Drives the [`XRInteractorState`] of an interactor from input.
+///
+/// Select sets the interactor state, activate sends [`ActivateEntered`] and [`ActivateExited`]
+/// for the interactable the interactor holds, e.g. to fire something that was picked up.
+#[derive(Component, Clone, Debug)]
+pub struct XrInteractorInput {
+    /// the hand the actions and gestures are read from
+    pub hand: Hand,
+    pub select: XrInteractorButton,
+    pub activate: XrInteractorButton,
+    activated: Option<Entity>,
+}
+
+impl XrInteractorInput {
+    /// selects with the grip and activates with the trigger of the controller in `hand`
+    pub fn new(hand: Hand) -> Self {
+        Self {
+            hand,
+            select: XrInteractorButton::new([XrInputSource::Action(SELECT_ACTION.into())]),
+            activate: XrInteractorButton::new([XrInputSource::Action(ACTIVATE_ACTION.into())]),
+            activated: None,
+        }
+    }
+
+    /// selects by pinching with `hand`, needs the
+    /// [`HandGesturePlugin`](super::gestures::HandGesturePlugin)
+    pub fn pinch(hand: Hand) -> Self {
+        Self {
+            select: XrInteractorButton::new([XrInputSource::Pinch]),
+            activate: XrInteractorButton::none(),
+            ..Self::new(hand)
+        }
+    }
+
+    pub fn with_select(mut self, select: XrInteractorButton) -> Self {
+        self.select = select;
+        self
+    }
+
+    pub fn with_activate(mut self, activate: XrInteractorButton) -> Self {
+        self.activate = activate;
+        self
+    }
+
+    /// the interactable that is activated right now
+    pub fn activated(&self) -> Option<Entity> {
+        self.activated
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ActivateEntered {
+    pub interactor: Entity,
+    pub interactable: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ActivateExited {
+    pub interactor: Entity,
+    pub interactable: Entity,
+}
+
+pub fn setup_interactor_actions(
+    mut commands: Commands,
+    config: Res<XrInteractorInputConfig>,
+    instance: Res<XrInstance>,
+    mut action_sets: ResMut<ActionSets>,
+    mut bindings: ResMut<XrSuggestedBindings>,
+) {
+    init_subaction_path(&instance);
+    let hands = [subaction_path(Hand::Left), subaction_path(Hand::Right)];
+    let action_set = match instance.create_action_set("interactions", "Interactions", 0) {
+        Ok(action_set) => action_set,
+        Err(err) => {
+            warn!("unable to create the interaction actions: {}", err);
+            return;
+        }
+    };
+    let mut actions = HashMap::new();
+    for input in config.actions.iter() {
+        let action = match input.kind {
+            XrInteractorActionKind::Value => action_set
+                .create_action::<f32>(&input.name, &input.name, &hands)
+                .map(|action| {
+                    for (profile, path) in input.bindings.iter() {
+                        bindings.add_handed(profile, &action, path);
+                    }
+                    InteractorAction::Value(action)
+                }),
+            XrInteractorActionKind::Click => action_set
+                .create_action::<bool>(&input.name, &input.name, &hands)
+                .map(|action| {
+                    for (profile, path) in input.bindings.iter() {
+                        bindings.add_handed(profile, &action, path);
+                    }
+                    InteractorAction::Click(action)
+                }),
+        };
+        match action {
+            Ok(action) => {
+                actions.insert(input.name.clone(), action);
+            }
+            Err(err) => {
+                warn!(
+                    "unable to create interaction action {}: {}",
+                    input.name, err
+                );
+                return;
+            }
+        }
+    }
+    action_sets.0.push(action_set);
+    commands.insert_resource(XrInteractorActions { actions });
+}
+
+pub fn update_interactor_inputs(
+    actions: Option<Res<XrInteractorActions>>,
+    session: Res<XrSession>,
+    hands: Query<(&Hand, Option<&PinchStrength>, Option<&GrabStrength>), With<OpenXRHand>>,
+    mut interactors: Query<(&mut XrInteractorInput, &mut XRInteractorState)>,
+) {
+    for (mut input, mut state) in interactors.iter_mut() {
+        let hand = input.hand;
+        let (pinch, grab) = hands
+            .iter()
+            .find(|(hand_component, _, _)| **hand_component == hand)
+            .map_or((0.0, 0.0), |(_, pinch, grab)| {
+                (
+                    pinch.map_or(0.0, |pinch| pinch.0),
+                    grab.map_or(0.0, |grab| grab.0),
+                )
+            });
+        let value = |source: &XrInputSource| match source {
+            XrInputSource::Action(name) => match actions.as_ref() {
+                Some(actions) => actions.value(&session, name, hand),
+                None => 0.0,
+            },
+            XrInputSource::Pinch => pinch,
+            XrInputSource::Grab => grab,
+        };
+        let select = input.select.sources.iter().map(value).fold(0.0, f32::max);
+        let activate = input.activate.sources.iter().map(value).fold(0.0, f32::max);
+        input.select.update(select);
+        input.activate.update(activate);
+        let new_state = match input.select.is_active() {
+            true => XRInteractorState::Selecting,
+            false => XRInteractorState::Idle,
+        };
+        if *state != new_state {
+            *state = new_state;
+        }
+    }
+}
+
+/// Activates the selected interactable while activate is active, runs once the selections of
+/// this frame are resolved
+pub fn update_interactor_activation(
+    mut interactors: Query<(Entity, &mut XrInteractorInput, &XRSelection)>,
+    mut activate_entered: EventWriter<ActivateEntered>,
+    mut activate_exited: EventWriter<ActivateExited>,
+) {
+    for (interactor, mut input, selection) in interactors.iter_mut() {
+        let target = match (input.activate.is_active(), *selection) {
+            (true, XRSelection::Full(interactable)) => Some(interactable),
+            _ => None,
+        };
+        if input.activated == target {
+            continue;
+        }
+        if let Some(interactable) = input.activated {
+            activate_exited.send(ActivateExited {
+                interactor,
+                interactable,
+            });
+        }
+        if let Some(interactable) = target {
+            activate_entered.send(ActivateEntered {
+                interactor,
+                interactable,
+            });
+        }
+        input.activated = target;
+    }
+}
@@ -0,0 +1,559 @@
+use std::f32::consts::PI;
+
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::render::view::RenderLayers;
+use openxr as xr;
+
+use crate::resources::{XrInstance, XrSession};
+
+use super::{
+    actions::{attach_action_sets, XrSuggestedBindings},
+    hand_emulation::{HTC_VIVE_PROFILE, VALVE_INDEX_PROFILE},
+    oculus_touch::{
+        init_subaction_path, setup_oculus_controller, subaction_path, ActionSets,
+        OCULUS_TOUCH_PROFILE,
+    },
+    teleport::{TeleportLocomotion, TeleportState},
+    trackers::{
+        head_transform, OpenXREyes, OpenXRLeftController, OpenXRLeftEye, OpenXRRightController,
+        OpenXRTrackingRoot,
+    },
+    Hand,
+};
+
+/// Moves and turns the [`OpenXRTrackingRoot`] with the thumbsticks.
+///
+/// Every move is collected in [`XrLocomotionMoves`] before it is applied, so systems in
+/// [`XrLocomotionSet::Validate`] can change or veto it, e.g. to keep the player out of walls.
+#[derive(Default)]
+pub struct XrLocomotionPlugin;
+
+impl Plugin for XrLocomotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<XrLocomotionConfig>()
+            .init_resource::<XrLocomotionMoves>()
+            .init_resource::<LocomotionState>()
+            .add_event::<XrLocomotionMoved>()
+            .configure_sets(
+                Update,
+                (
+                    XrLocomotionSet::Input,
+                    XrLocomotionSet::Validate,
+                    XrLocomotionSet::Apply,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Startup,
+                (
+                    setup_locomotion_actions
+                        .after(setup_oculus_controller)
+                        .before(attach_action_sets),
+                    setup_vignette,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_locomotion.in_set(XrLocomotionSet::Input),
+                    (
+                        apply_locomotion_moves,
+                        spawn_eye_vignettes,
+                        update_vignettes,
+                    )
+                        .chain()
+                        .in_set(XrLocomotionSet::Apply),
+                ),
+            );
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XrLocomotionSet {
+    /// reads the input and adds the moves of this frame to [`XrLocomotionMoves`]
+    Input,
+    /// change or veto the moves here before they are applied
+    Validate,
+    /// moves the tracking root and sends [`XrLocomotionMoved`]
+    Apply,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrLocomotionDirection {
+    /// pushing the stick forward moves where the head looks
+    Head,
+    /// pushing the stick forward moves where the controller of the moving hand points
+    Hand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XrTurnMode {
+    /// turns continuously, in radians per second
+    Smooth {
+        speed: f32,
+    },
+    /// turns by `angle` radians every time the stick is pushed to the side
+    Snap {
+        angle: f32,
+    },
+    Disabled,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct XrLocomotionConfig {
+    /// the thumbstick that moves
+    pub move_hand: Hand,
+    /// the thumbstick that turns
+    pub turn_hand: Hand,
+    pub direction: XrLocomotionDirection,
+    /// top speed in meters per second
+    pub speed: f32,
+    /// how fast the top speed is reached, in meters per second squared
+    pub acceleration: f32,
+    /// how fast the player stops after letting go of the stick, in meters per second squared
+    pub deceleration: f32,
+    pub turn: XrTurnMode,
+    /// sticks closer to the center than this are ignored
+    pub deadzone: f32,
+    /// pushing the stick to the side past this snap turns
+    pub snap_threshold: f32,
+    /// the stick has to go back below this before the next snap turn
+    pub snap_release_threshold: f32,
+    /// how much the view narrows while moving and turning, from 0 to 1, 0 turns it off
+    pub vignette: f32,
+    /// interaction profile and input path of the thumbsticks, relative to the hand.
+    ///
+    /// The action is created at startup, so changes after that don't do anything.
+    pub stick_bindings: Vec<(String, String)>,
+}
+
+impl Default for XrLocomotionConfig {
+    fn default() -> Self {
+        Self {
+            move_hand: Hand::Left,
+            turn_hand: Hand::Right,
+            direction: XrLocomotionDirection::Head,
+            speed: 1.5,
+            acceleration: 6.0,
+            deceleration: 10.0,
+            turn: XrTurnMode::Snap {
+                angle: 45.0 * (PI / 180.0),
+            },
+            deadzone: 0.2,
+            snap_threshold: 0.7,
+            snap_release_threshold: 0.3,
+            vignette: 0.6,
+            stick_bindings: vec![
+                (OCULUS_TOUCH_PROFILE.into(), "input/thumbstick".into()),
+                (VALVE_INDEX_PROFILE.into(), "input/thumbstick".into()),
+                (HTC_VIVE_PROFILE.into(), "input/trackpad".into()),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrLocomotionKind {
+    Move,
+    Turn,
+    Teleport,
+}
+
+/// One change of the tracking root, it turns around `pivot` first and moves after that
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XrLocomotionMove {
+    pub kind: XrLocomotionKind,
+    pub translation: Vec3,
+    /// rotation around the y axis in radians
+    pub yaw: f32,
+    /// the point in world space the turn goes around, usually the head
+    pub pivot: Vec3,
+    vetoed: bool,
+}
+
+impl XrLocomotionMove {
+    pub fn new(kind: XrLocomotionKind, translation: Vec3, yaw: f32, pivot: Vec3) -> Self {
+        Self {
+            kind,
+            translation,
+            yaw,
+            pivot,
+            vetoed: false,
+        }
+    }
+
+    /// stops the move from being applied
+    pub fn veto(&mut self) {
+        self.vetoed = true;
+    }
+
+    pub fn is_vetoed(&self) -> bool {
+        self.vetoed
+    }
+
+    pub fn apply(&self, root: &mut Transform) {
+        root.rotate_around(self.pivot, Quat::from_rotation_y(self.yaw));
+        root.translation += self.translation;
+    }
+}
+
+/// The moves of this frame, applied and cleared in [`XrLocomotionSet::Apply`]
+#[derive(Resource, Default, Debug)]
+pub struct XrLocomotionMoves {
+    moves: Vec<XrLocomotionMove>,
+}
+
+impl XrLocomotionMoves {
+    pub fn push(&mut self, locomotion_move: XrLocomotionMove) {
+        self.moves.push(locomotion_move);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &XrLocomotionMove> {
+        self.moves.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut XrLocomotionMove> {
+        self.moves.iter_mut()
+    }
+}
+
+/// Sent for every move that was applied
+#[derive(Event, Clone, Copy, Debug)]
+pub struct XrLocomotionMoved(pub XrLocomotionMove);
+
+#[derive(Resource)]
+struct LocomotionActions {
+    stick: xr::Action<xr::Vector2f>,
+}
+
+#[derive(Resource, Default)]
+struct LocomotionState {
+    velocity: Vec3,
+    snap_released: bool,
+    turned: bool,
+    vignette: f32,
+}
+
+#[derive(Resource)]
+struct Vignette {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+// on the vignette of each eye
+#[derive(Component)]
+struct VignettePart;
+
+// on the eye cameras that have their vignette
+#[derive(Component)]
+struct HasVignette;
+
+//angles of the inner edge of the vignette from the view direction, open and fully closed,
+//typical headsets show around 45 to 50 degrees to each side
+const VIGNETTE_OPEN: f32 = 55.0 * (PI / 180.0);
+const VIGNETTE_CLOSED: f32 = 20.0 * (PI / 180.0);
+//each eye only sees its own vignette, the other one is a few centimeters off
+const VIGNETTE_LEFT_LAYER: u8 = 30;
+const VIGNETTE_RIGHT_LAYER: u8 = 31;
+//how fast the vignette follows the movement, per second
+const VIGNETTE_SPEED: f32 = 4.0;
+//just past the near plane of the xr cameras
+const VIGNETTE_DISTANCE: f32 = 0.15;
+
+fn setup_locomotion_actions(
+    mut commands: Commands,
+    config: Res<XrLocomotionConfig>,
+    instance: Res<XrInstance>,
+    mut action_sets: ResMut<ActionSets>,
+    mut bindings: ResMut<XrSuggestedBindings>,
+) {
+    init_subaction_path(&instance);
+    let hands = [subaction_path(Hand::Left), subaction_path(Hand::Right)];
+    let action = instance
+        .create_action_set("locomotion", "Locomotion", 0)
+        .and_then(|action_set| {
+            let stick =
+                action_set.create_action::<xr::Vector2f>("locomotion_stick", "Move", &hands)?;
+            Ok((action_set, stick))
+        });
+    match action {
+        Ok((action_set, stick)) => {
+            for (profile, path) in config.stick_bindings.iter() {
+                bindings.add_handed(profile, &stick, path);
+            }
+            action_sets.0.push(action_set);
+            commands.insert_resource(LocomotionActions { stick });
+        }
+        Err(err) => warn!("unable to create the locomotion actions: {}", err),
+    }
+}
+
+// the stick of `hand` with the deadzone cut out, so it still goes from 0 to 1
+fn read_stick(
+    actions: &LocomotionActions,
+    session: &xr::Session<xr::AnyGraphics>,
+    hand: Hand,
+    deadzone: f32,
+) -> Vec2 {
+    let stick = match actions.stick.state(session, subaction_path(hand)) {
+        Ok(state) if state.is_active => Vec2::new(state.current_state.x, state.current_state.y),
+        _ => return Vec2::ZERO,
+    };
+    let length = stick.length();
+    match length > deadzone {
+        true => stick * (((length - deadzone) / (1.0 - deadzone)).min(1.0) / length),
+        false => Vec2::ZERO,
+    }
+}
+
+// rotation around the y axis that faces where `rotation` looks, ignoring the pitch
+pub(crate) fn flat_yaw(rotation: Quat) -> f32 {
+    let forward = rotation * Vec3::NEG_Z;
+    //looking straight up or down the top of the head points forward or backward instead
+    let forward = match Vec2::new(forward.x, forward.z).length() > 0.1 {
+        true => forward,
+        false => rotation * Vec3::Y * -forward.y.signum(),
+    };
+    f32::atan2(-forward.x, -forward.z)
+}
+
+fn update_locomotion(
+    time: Res<Time>,
+    config: Res<XrLocomotionConfig>,
+    actions: Option<Res<LocomotionActions>>,
+    session: Res<XrSession>,
+    mut state: ResMut<LocomotionState>,
+    mut moves: ResMut<XrLocomotionMoves>,
+    teleport: Option<Res<TeleportLocomotion>>,
+    teleport_state: Option<Res<TeleportState>>,
+    eyes: Query<&GlobalTransform, OpenXREyes>,
+    controllers: Query<
+        (&GlobalTransform, Has<OpenXRLeftController>),
+        Or<(With<OpenXRLeftController>, With<OpenXRRightController>)>,
+    >,
+) {
+    let actions = match actions {
+        Some(actions) => actions,
+        None => return,
+    };
+    let head = match head_transform(&eyes) {
+        Some(head) => head,
+        None => return,
+    };
+    let delta = time.delta_seconds();
+
+    let stick = read_stick(&actions, &session, config.move_hand, config.deadzone);
+    let reference = match config.direction {
+        XrLocomotionDirection::Head => Some(head.rotation),
+        XrLocomotionDirection::Hand => controllers
+            .iter()
+            .find(|(_, left)| *left == (config.move_hand == Hand::Left))
+            .map(|(transform, _)| transform.compute_transform().rotation),
+    };
+    let target_velocity = match reference {
+        Some(reference) => {
+            Quat::from_rotation_y(flat_yaw(reference))
+                * Vec3::new(stick.x, 0.0, -stick.y)
+                * config.speed
+        }
+        None => Vec3::ZERO,
+    };
+    let rate = match target_velocity.length() >= state.velocity.length() {
+        true => config.acceleration,
+        false => config.deceleration,
+    };
+    let velocity =
+        state.velocity + (target_velocity - state.velocity).clamp_length_max(rate * delta);
+    state.velocity = velocity;
+    if velocity != Vec3::ZERO {
+        moves.push(XrLocomotionMove::new(
+            XrLocomotionKind::Move,
+            velocity * delta,
+            0.0,
+            head.translation,
+        ));
+    }
+
+    //the stick aims the teleport while it is in use, it shouldn't turn as well
+    let teleport_in_use = match (teleport, teleport_state) {
+        (Some(teleport), Some(teleport_state)) => {
+            teleport.hand == config.turn_hand
+                && (teleport_state.is_aiming() || teleport_state.is_teleporting())
+        }
+        _ => false,
+    };
+    let turn = match teleport_in_use {
+        true => 0.0,
+        false => read_stick(&actions, &session, config.turn_hand, config.deadzone).x,
+    };
+    let yaw = match config.turn {
+        XrTurnMode::Smooth { speed } => -turn * speed * delta,
+        XrTurnMode::Snap { angle } => {
+            let snap = state.snap_released && turn.abs() >= config.snap_threshold;
+            match (snap, turn.abs() < config.snap_release_threshold) {
+                (true, _) => {
+                    state.snap_released = false;
+                    -turn.signum() * angle
+                }
+                (false, released) => {
+                    state.snap_released |= released;
+                    0.0
+                }
+            }
+        }
+        XrTurnMode::Disabled => 0.0,
+    };
+    state.turned = yaw != 0.0;
+    if state.turned {
+        moves.push(XrLocomotionMove::new(
+            XrLocomotionKind::Turn,
+            Vec3::ZERO,
+            yaw,
+            head.translation,
+        ));
+    }
+}
+
+pub fn apply_locomotion_moves(
+    mut moves: ResMut<XrLocomotionMoves>,
+    mut tracking_root: Query<&mut Transform, With<OpenXRTrackingRoot>>,
+    mut moved: EventWriter<XrLocomotionMoved>,
+) {
+    let mut root = match tracking_root.get_single_mut() {
+        Ok(root) => root,
+        Err(_) => {
+            moves.moves.clear();
+            return;
+        }
+    };
+    for locomotion_move in moves.moves.drain(..) {
+        if locomotion_move.is_vetoed() {
+            continue;
+        }
+        locomotion_move.apply(&mut root);
+        moved.send(XrLocomotionMoved(locomotion_move));
+    }
+}
+
+fn setup_vignette(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(Vignette {
+        mesh: meshes.add(vignette_mesh()),
+        material: materials.add(StandardMaterial {
+            unlit: true,
+            cull_mode: None,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        }),
+    });
+}
+
+// the vignettes are children of the eye cameras, so they follow this frame's moves and turns
+fn spawn_eye_vignettes(
+    mut commands: Commands,
+    vignette: Res<Vignette>,
+    eyes: Query<
+        (Entity, Has<OpenXRLeftEye>, Option<&RenderLayers>),
+        (OpenXREyes, Without<HasVignette>),
+    >,
+) {
+    for (eye, left, layers) in eyes.iter() {
+        let layer = match left {
+            true => VIGNETTE_LEFT_LAYER,
+            false => VIGNETTE_RIGHT_LAYER,
+        };
+        let part = commands
+            .spawn((
+                PbrBundle {
+                    mesh: vignette.mesh.clone(),
+                    material: vignette.material.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                NotShadowCaster,
+                RenderLayers::layer(layer),
+                VignettePart,
+            ))
+            .id();
+        commands
+            .entity(eye)
+            .insert((layers.copied().unwrap_or_default().with(layer), HasVignette))
+            .add_child(part);
+    }
+}
+
+fn update_vignettes(
+    time: Res<Time>,
+    config: Res<XrLocomotionConfig>,
+    mut state: ResMut<LocomotionState>,
+    mut vignettes: Query<(&mut Transform, &mut Visibility), With<VignettePart>>,
+) {
+    //turning closes it at once, moving closes it as far as the player is going
+    let target = match state.turned {
+        true => 1.0,
+        false => match config.speed > 0.0 {
+            true => (state.velocity.length() / config.speed).min(1.0),
+            false => 0.0,
+        },
+    };
+    let step = VIGNETTE_SPEED * time.delta_seconds();
+    state.vignette = match state.turned {
+        true => target,
+        false => state.vignette + (target - state.vignette).clamp(-step, step),
+    };
+    let closed = state.vignette * config.vignette;
+    let radius = (VIGNETTE_OPEN + (VIGNETTE_CLOSED - VIGNETTE_OPEN) * closed).tan();
+    let new_transform = Transform::from_scale(Vec3::new(
+        radius * VIGNETTE_DISTANCE,
+        radius * VIGNETTE_DISTANCE,
+        VIGNETTE_DISTANCE,
+    ));
+    let new_visibility = match closed > 0.0 {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    for (mut transform, mut visibility) in vignettes.iter_mut() {
+        if *transform != new_transform {
+            *transform = new_transform;
+        }
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
+
+// a flat ring one unit in front of the origin, clear inside of radius 1 and black from a bit
+// further out to far past the edge of any field of view, all of it in front of the near plane
+fn vignette_mesh() -> Mesh {
+    const SEGMENTS: u32 = 32;
+    let rings = [(1.0, -1.0, 0.0), (1.4, -1.0, 1.0), (40.0, -1.0, 1.0)];
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    for (radius, z, alpha) in rings {
+        for segment in 0..SEGMENTS {
+            let angle = segment as f32 / SEGMENTS as f32 * 2.0 * PI;
+            positions.push([angle.cos() * radius, angle.sin() * radius, z]);
+            colors.push([0.0, 0.0, 0.0, alpha]);
+        }
+    }
+    let mut indices = Vec::new();
+    for ring in 0..rings.len() as u32 - 1 {
+        for segment in 0..SEGMENTS {
+            let a = ring * SEGMENTS + segment;
+            let b = ring * SEGMENTS + (segment + 1) % SEGMENTS;
+            let (c, d) = (a + SEGMENTS, b + SEGMENTS);
+            indices.extend_from_slice(&[a, b, c, b, d, c]);
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
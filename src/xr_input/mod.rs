@@ -1,23 +1,43 @@
+pub mod actions;
 pub mod controllers;
 pub mod debug_gizmos;
+pub mod gestures;
+pub mod grabbable;
 pub mod interactions;
+pub mod interactor_input;
+pub mod locomotion;
 pub mod oculus_touch;
-pub mod prototype_locomotion;
+pub mod play_area;
+pub mod ray_visuals;
+pub mod sockets;
+pub mod teleport;
 pub mod trackers;
+pub mod ui_panel;
 pub mod xr_camera;
 pub mod hand_poses;
+pub mod hand_pose_matching;
 pub mod hand;
+pub mod hand_emulation;
+pub mod hand_interactions;
+pub mod hand_mesh;
+pub mod hand_physics;
+#[cfg(feature = "rapier")]
+pub mod hand_rapier;
 pub mod handtracking;
 
 use crate::resources::XrSession;
-use crate::xr_begin_frame;
+use crate::{locate_views, XrFrameSet};
+use crate::xr_input::actions::{attach_action_sets, XrSuggestedBindings};
 use crate::xr_input::controllers::XrControllerType;
 use crate::xr_input::oculus_touch::{setup_oculus_controller, ActionSets};
-use crate::xr_input::xr_camera::{xr_camera_head_sync, Eye, XRProjection, XrCameraBundle};
+use crate::xr_input::play_area::XrPlayAreaPlugin;
+use crate::xr_input::xr_camera::{
+    xr_camera_head_sync, xr_camera_should_render, Eye, XRProjection, XrCameraBundle,
+};
 use bevy::app::{App, PostUpdate, Startup};
 use bevy::log::warn;
 use bevy::prelude::{BuildChildren, IntoSystemConfigs, Component};
-use bevy::prelude::{Commands, Plugin, PreUpdate, Quat, Res, SpatialBundle, Update, Vec3};
+use bevy::prelude::{Commands, Plugin, PreUpdate, Quat, Res, SpatialBundle, Vec3};
 use bevy::render::camera::CameraProjectionPlugin;
 use bevy::render::view::{update_frusta, VisibilitySystems};
 use bevy::transform::TransformSystem;
@@ -46,17 +66,35 @@ impl OpenXrInput {
 impl Plugin for OpenXrInput {
     fn build(&self, app: &mut App) {
         app.add_plugins(CameraProjectionPlugin::<XRProjection>::default());
+        app.add_plugins(XrPlayAreaPlugin);
+        app.init_resource::<XrSuggestedBindings>();
         match self.controller_type {
             XrControllerType::OculusTouch => {
-                app.add_systems(Startup, setup_oculus_controller);
+                app.add_systems(Startup, setup_oculus_controller.before(attach_action_sets));
             }
         }
+        app.add_systems(Startup, attach_action_sets);
         //adopt any new trackers
         app.add_systems(PreUpdate, adopt_open_xr_trackers);
-        app.add_systems(PreUpdate, action_set_system);
-        app.add_systems(PreUpdate, xr_camera_head_sync.after(xr_begin_frame));
-        //update controller trackers
-        app.add_systems(Update, update_open_xr_controllers);
+        app.add_systems(
+            PreUpdate,
+            (
+                action_set_system.before(update_open_xr_controllers),
+                xr_camera_head_sync.after(locate_views),
+                xr_camera_should_render,
+                update_open_xr_controllers,
+            )
+                .in_set(XrFrameSet::LocatePoses),
+        );
+        //locate the cameras and controllers again right before rendering
+        app.add_systems(
+            PostUpdate,
+            (
+                xr_camera_head_sync.after(locate_views),
+                update_open_xr_controllers,
+            )
+                .in_set(XrFrameSet::LateLatch),
+        );
         app.add_systems(
             PostUpdate,
             update_frusta::<XRProjection>
@@ -82,7 +120,7 @@ fn setup_xr_cameras(mut commands: Commands) {
     commands.entity(tracking_root).push_children(&[right, left]);
 }
 
-fn action_set_system(action_sets: Res<ActionSets>, session: Res<XrSession>) {
+pub fn action_set_system(action_sets: Res<ActionSets>, session: Res<XrSession>) {
     let mut active_action_sets = vec![];
     for i in &action_sets.0 {
         active_action_sets.push(openxr::ActiveActionSet::new(i));
@@ -1,32 +1,33 @@
 use crate::input::XrInput;
 use crate::resources::{XrInstance, XrSession};
+use crate::xr_input::actions::XrSuggestedBindings;
 use crate::xr_input::controllers::{Handed, Touchable};
 use crate::xr_input::Hand;
-use bevy::prelude::{Commands, Res, Resource};
+use bevy::prelude::{Commands, Res, ResMut, Resource};
 use openxr::{
-    Action, ActionSet, AnyGraphics, Binding, FrameState, Haptic, Instance, Path, Posef, Session,
-    Space, SpaceLocation, SpaceVelocity,
+    Action, ActionSet, AnyGraphics, FrameState, Haptic, Instance, Path, Posef, Session, Space,
+    SpaceLocation, SpaceVelocity,
 };
 
 use std::sync::OnceLock;
 
+pub const OCULUS_TOUCH_PROFILE: &str = "/interaction_profiles/oculus/touch_controller";
+
 pub fn setup_oculus_controller(
     mut commands: Commands,
     instance: Res<XrInstance>,
     session: Res<XrSession>,
+    mut action_sets: ResMut<ActionSets>,
+    mut bindings: ResMut<XrSuggestedBindings>,
 ) {
-    let mut action_sets = vec![];
     let oculus_controller = OculusController::new(
         Instance::clone(&instance),
         Session::clone(&session),
-        &mut action_sets,
+        &mut action_sets.0,
+        &mut bindings,
     )
     .unwrap();
-    session
-        .attach_action_sets(&action_sets.iter().map(|a| a).collect::<Vec<_>>())
-        .unwrap();
     commands.insert_resource(oculus_controller);
-    commands.insert_resource(ActionSets(action_sets));
 }
 
 #[derive(Resource, Clone)]
@@ -264,6 +265,7 @@ impl OculusController {
         instance: Instance,
         session: Session<AnyGraphics>,
         action_sets: &mut Vec<ActionSet>,
+        bindings: &mut XrSuggestedBindings,
     ) -> anyhow::Result<Self> {
         let action_set =
             instance.create_action_set("oculus_input", "Oculus Touch Controller Input", 0)?;
@@ -330,136 +332,146 @@ impl OculusController {
                 &hands,
             )?,
         };
-        let i = instance;
-        i.suggest_interaction_profile_bindings(
-            i.string_to_path("/interaction_profiles/oculus/touch_controller")?,
-            &[
-                Binding::new(
-                    &this.grip_pose,
-                    i.string_to_path("/user/hand/left/input/grip/pose")?,
-                ),
-                Binding::new(
-                    &this.grip_pose,
-                    i.string_to_path("/user/hand/right/input/grip/pose")?,
-                ),
-                Binding::new(
-                    &this.aim_pose,
-                    i.string_to_path("/user/hand/left/input/aim/pose")?,
-                ),
-                Binding::new(
-                    &this.aim_pose,
-                    i.string_to_path("/user/hand/left/input/aim/pose")?,
-                ),
-                Binding::new(
-                    &this.squeeze,
-                    i.string_to_path("/user/hand/left/input/squeeze/value")?,
-                ),
-                Binding::new(
-                    &this.squeeze,
-                    i.string_to_path("/user/hand/right/input/squeeze/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.inner,
-                    i.string_to_path("/user/hand/right/input/trigger/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.inner,
-                    i.string_to_path("/user/hand/left/input/trigger/value")?,
-                ),
-                Binding::new(
-                    &this.trigger.touch,
-                    i.string_to_path("/user/hand/right/input/trigger/touch")?,
-                ),
-                Binding::new(
-                    &this.trigger.touch,
-                    i.string_to_path("/user/hand/left/input/trigger/touch")?,
-                ),
-                Binding::new(
-                    &this.haptic_feedback,
-                    i.string_to_path("/user/hand/right/output/haptic")?,
-                ),
-                Binding::new(
-                    &this.haptic_feedback,
-                    i.string_to_path("/user/hand/left/output/haptic")?,
-                ),
-                Binding::new(
-                    &this.x_button.inner,
-                    i.string_to_path("/user/hand/left/input/x/click")?,
-                ),
-                Binding::new(
-                    &this.x_button.touch,
-                    i.string_to_path("/user/hand/left/input/x/touch")?,
-                ),
-                Binding::new(
-                    &this.y_button.inner,
-                    i.string_to_path("/user/hand/left/input/y/click")?,
-                ),
-                Binding::new(
-                    &this.y_button.touch,
-                    i.string_to_path("/user/hand/left/input/y/touch")?,
-                ),
-                Binding::new(
-                    &this.menu_button,
-                    i.string_to_path("/user/hand/left/input/menu/click")?,
-                ),
-                Binding::new(
-                    &this.a_button.inner,
-                    i.string_to_path("/user/hand/right/input/a/click")?,
-                ),
-                Binding::new(
-                    &this.a_button.touch,
-                    i.string_to_path("/user/hand/right/input/a/touch")?,
-                ),
-                Binding::new(
-                    &this.b_button.inner,
-                    i.string_to_path("/user/hand/right/input/b/click")?,
-                ),
-                Binding::new(
-                    &this.b_button.touch,
-                    i.string_to_path("/user/hand/right/input/b/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_x,
-                    i.string_to_path("/user/hand/left/input/thumbstick/x")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_x,
-                    i.string_to_path("/user/hand/right/input/thumbstick/x")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_y,
-                    i.string_to_path("/user/hand/left/input/thumbstick/y")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_y,
-                    i.string_to_path("/user/hand/right/input/thumbstick/y")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_click,
-                    i.string_to_path("/user/hand/left/input/thumbstick/click")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_click,
-                    i.string_to_path("/user/hand/right/input/thumbstick/click")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_touch,
-                    i.string_to_path("/user/hand/left/input/thumbstick/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbstick_touch,
-                    i.string_to_path("/user/hand/right/input/thumbstick/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbrest_touch,
-                    i.string_to_path("/user/hand/left/input/thumbrest/touch")?,
-                ),
-                Binding::new(
-                    &this.thumbrest_touch,
-                    i.string_to_path("/user/hand/right/input/thumbrest/touch")?,
-                ),
-            ],
-        )?;
+        let profile = OCULUS_TOUCH_PROFILE;
+        bindings.add(profile, &this.grip_pose, "/user/hand/left/input/grip/pose");
+        bindings.add(profile, &this.grip_pose, "/user/hand/right/input/grip/pose");
+        bindings.add(profile, &this.aim_pose, "/user/hand/left/input/aim/pose");
+        bindings.add(profile, &this.aim_pose, "/user/hand/right/input/aim/pose");
+        bindings.add(
+            profile,
+            &this.squeeze,
+            "/user/hand/left/input/squeeze/value",
+        );
+        bindings.add(
+            profile,
+            &this.squeeze,
+            "/user/hand/right/input/squeeze/value",
+        );
+        bindings.add(
+            profile,
+            &this.trigger.inner,
+            "/user/hand/right/input/trigger/value",
+        );
+        bindings.add(
+            profile,
+            &this.trigger.inner,
+            "/user/hand/left/input/trigger/value",
+        );
+        bindings.add(
+            profile,
+            &this.trigger.touch,
+            "/user/hand/right/input/trigger/touch",
+        );
+        bindings.add(
+            profile,
+            &this.trigger.touch,
+            "/user/hand/left/input/trigger/touch",
+        );
+        bindings.add(
+            profile,
+            &this.haptic_feedback,
+            "/user/hand/right/output/haptic",
+        );
+        bindings.add(
+            profile,
+            &this.haptic_feedback,
+            "/user/hand/left/output/haptic",
+        );
+        bindings.add(
+            profile,
+            &this.x_button.inner,
+            "/user/hand/left/input/x/click",
+        );
+        bindings.add(
+            profile,
+            &this.x_button.touch,
+            "/user/hand/left/input/x/touch",
+        );
+        bindings.add(
+            profile,
+            &this.y_button.inner,
+            "/user/hand/left/input/y/click",
+        );
+        bindings.add(
+            profile,
+            &this.y_button.touch,
+            "/user/hand/left/input/y/touch",
+        );
+        bindings.add(
+            profile,
+            &this.menu_button,
+            "/user/hand/left/input/menu/click",
+        );
+        bindings.add(
+            profile,
+            &this.a_button.inner,
+            "/user/hand/right/input/a/click",
+        );
+        bindings.add(
+            profile,
+            &this.a_button.touch,
+            "/user/hand/right/input/a/touch",
+        );
+        bindings.add(
+            profile,
+            &this.b_button.inner,
+            "/user/hand/right/input/b/click",
+        );
+        bindings.add(
+            profile,
+            &this.b_button.touch,
+            "/user/hand/right/input/b/touch",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_x,
+            "/user/hand/left/input/thumbstick/x",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_x,
+            "/user/hand/right/input/thumbstick/x",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_y,
+            "/user/hand/left/input/thumbstick/y",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_y,
+            "/user/hand/right/input/thumbstick/y",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_click,
+            "/user/hand/left/input/thumbstick/click",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_click,
+            "/user/hand/right/input/thumbstick/click",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_touch,
+            "/user/hand/left/input/thumbstick/touch",
+        );
+        bindings.add(
+            profile,
+            &this.thumbstick_touch,
+            "/user/hand/right/input/thumbstick/touch",
+        );
+        bindings.add(
+            profile,
+            &this.thumbrest_touch,
+            "/user/hand/left/input/thumbrest/touch",
+        );
+        bindings.add(
+            profile,
+            &this.thumbrest_touch,
+            "/user/hand/right/input/thumbrest/touch",
+        );
 
         action_sets.push(action_set);
         Ok(this)
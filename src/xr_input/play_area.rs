@@ -0,0 +1,183 @@
+use std::sync::atomic::Ordering;
+
+use bevy::prelude::*;
+use openxr as xr;
+
+use crate::{
+    input::{pose_to_transform, XrInput, XrReferenceSpaceChanged},
+    resources::{XrFrameState, XrSession, XrSessionRunning},
+    XrFrameSet,
+};
+
+use super::trackers::OpenXRTrackingRoot;
+
+/// Keeps [`XrPlayArea`] up to date with the bounds the user set up in the runtime
+#[derive(Default)]
+pub struct XrPlayAreaPlugin;
+
+impl Plugin for XrPlayAreaPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(XrPlayArea::default())
+            .add_systems(Startup, setup_play_area_space)
+            .add_systems(PreUpdate, update_play_area.in_set(XrFrameSet::LocatePoses));
+    }
+}
+
+/// draws the play area boundary on the floor
+#[derive(Default)]
+pub struct XrPlayAreaDebugRenderer;
+
+impl Plugin for XrPlayAreaDebugRenderer {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_play_area);
+    }
+}
+
+/// The play area (chaperone / guardian) of the user.
+///
+/// The boundary is given in the stage space on the floor, `stage_pose` places
+/// that space inside the tracking space used for everything else.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct XrPlayArea {
+    /// width (x) and depth (z) of the rectangle centered on the stage origin,
+    /// `None` if the runtime has no bounds (yet)
+    pub rect: Option<Vec2>,
+    /// the boundary as a polygon in stage space, for now always the four corners of
+    /// `rect` as no vendor specific outline is queried
+    pub polygon: Vec<Vec2>,
+    /// the stage origin relative to the tracking root
+    pub stage_pose: Transform,
+}
+
+impl XrPlayArea {
+    /// the boundary polygon in world space
+    pub fn world_polygon(&self, tracking_root: &GlobalTransform) -> Vec<Vec3> {
+        let to_world = tracking_root.compute_matrix() * self.stage_pose.compute_matrix();
+        self.polygon
+            .iter()
+            .map(|p| to_world.transform_point3(Vec3::new(p.x, 0.0, p.y)))
+            .collect()
+    }
+
+    /// whether a point in world space lies inside the play area, ignoring its height
+    pub fn contains(&self, point: Vec3, tracking_root: &GlobalTransform) -> bool {
+        if self.polygon.len() < 3 {
+            return false;
+        }
+        let to_stage =
+            (tracking_root.compute_matrix() * self.stage_pose.compute_matrix()).inverse();
+        let local = to_stage.transform_point3(point);
+        polygon_contains(&self.polygon, Vec2::new(local.x, local.z))
+    }
+}
+
+fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool {
+    //even odd rule
+    let mut inside = false;
+    let mut prev = polygon[polygon.len() - 1];
+    for &current in polygon {
+        if (current.y > point.y) != (prev.y > point.y) {
+            let x = prev.x + (point.y - prev.y) / (current.y - prev.y) * (current.x - prev.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+        prev = current;
+    }
+    inside
+}
+
+fn rect_polygon(rect: Vec2) -> Vec<Vec2> {
+    let half = rect * 0.5;
+    vec![
+        Vec2::new(-half.x, -half.y),
+        Vec2::new(half.x, -half.y),
+        Vec2::new(half.x, half.y),
+        Vec2::new(-half.x, half.y),
+    ]
+}
+
+#[derive(Resource)]
+pub struct XrStageSpace(pub xr::Space);
+
+pub fn setup_play_area_space(mut commands: Commands, session: Res<XrSession>) {
+    match session.create_reference_space(xr::ReferenceSpaceType::STAGE, xr::Posef::IDENTITY) {
+        Ok(space) => commands.insert_resource(XrStageSpace(space)),
+        Err(err) => warn!(
+            "unable to create stage space, no play area available: {}",
+            err
+        ),
+    }
+}
+
+pub fn update_play_area(
+    mut play_area: ResMut<XrPlayArea>,
+    mut space_changed: EventReader<XrReferenceSpaceChanged>,
+    stage_space: Option<Res<XrStageSpace>>,
+    session: Res<XrSession>,
+    session_running: Res<XrSessionRunning>,
+    input: Res<XrInput>,
+    frame_state: Res<XrFrameState>,
+    mut was_running: Local<bool>,
+    mut warned: Local<bool>,
+) {
+    let stage_space = match stage_space {
+        Some(space) => space,
+        None => return,
+    };
+    //the runtime only reports bounds once the session is running, so ask again when it starts
+    let running = session_running.load(Ordering::Relaxed);
+    let started = running && !*was_running;
+    *was_running = running;
+    if space_changed.read().count() > 0 || started {
+        match session.reference_space_bounds_rect(xr::ReferenceSpaceType::STAGE) {
+            Ok(Some(extent)) => {
+                let rect = Vec2::new(extent.width, extent.height);
+                if play_area.rect != Some(rect) {
+                    info!("play area is {}m x {}m", rect.x, rect.y);
+                    play_area.rect = Some(rect);
+                    play_area.polygon = rect_polygon(rect);
+                }
+            }
+            Ok(None) => {
+                if play_area.rect.is_some() {
+                    play_area.rect = None;
+                    play_area.polygon.clear();
+                }
+            }
+            Err(err) => {
+                if !*warned {
+                    warn!("error getting play area bounds: {}", err);
+                    *warned = true;
+                }
+            }
+        }
+    }
+    let time = frame_state.predicted_display_time;
+    if let Ok(location) = stage_space.0.locate(&input.stage, time) {
+        if location.location_flags.contains(
+            xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID,
+        ) {
+            let stage_pose = pose_to_transform(location.pose);
+            if play_area.stage_pose != stage_pose {
+                play_area.stage_pose = stage_pose;
+            }
+        }
+    }
+}
+
+pub fn draw_play_area(
+    mut gizmos: Gizmos,
+    play_area: Res<XrPlayArea>,
+    tracking_root_query: Query<&GlobalTransform, With<OpenXRTrackingRoot>>,
+) {
+    let root = match tracking_root_query.get_single() {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+    let mut points = play_area.world_polygon(root);
+    if let Some(first) = points.first().copied() {
+        points.push(first);
+        gizmos.linestrip(points, Color::CYAN);
+    }
+}
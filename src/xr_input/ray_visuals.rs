@@ -0,0 +1,221 @@
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::render::render_resource::PrimitiveTopology;
+use bevy::render::view::NoFrustumCulling;
+
+use super::interactions::{XRInteractorState, XrInteractionSet, XrRayHit, XrRayPath};
+
+/// Renders the rays of ray interactors with an [`XrRayVisual`] as meshes, with a reticle where
+/// they hit something.
+///
+/// Unlike the interaction gizmos these are regular meshes, so they show up in release builds
+/// and can be lit or post processed like everything else.
+#[derive(Default)]
+pub struct XrRayVisualPlugin;
+
+impl Plugin for XrRayVisualPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (spawn_ray_visuals, update_ray_visuals)
+                .chain()
+                .in_set(XrInteractionSet::Feedback),
+        );
+    }
+}
+
+/// How the ray of a ray interactor looks, the line follows its [`XrRayPath`]
+#[derive(Component, Clone, Debug)]
+pub struct XrRayVisual {
+    /// width of the line in meters
+    pub width: f32,
+    pub idle_color: Color,
+    /// color while the ray hits an interactable
+    pub hover_color: Color,
+    pub select_color: Color,
+    /// radius of the ring at the hit point in meters, 0 hides it
+    pub reticle_radius: f32,
+}
+
+impl Default for XrRayVisual {
+    fn default() -> Self {
+        Self {
+            width: 0.004,
+            idle_color: Color::rgba(1.0, 1.0, 1.0, 0.6),
+            hover_color: Color::YELLOW,
+            select_color: Color::GREEN,
+            reticle_radius: 0.02,
+        }
+    }
+}
+
+// the line and reticle entities of a ray interactor
+#[derive(Component)]
+struct RayVisualPart {
+    interactor: Entity,
+    reticle: bool,
+}
+
+#[derive(Component)]
+struct RayVisualSpawned;
+
+fn spawn_ray_visuals(
+    mut commands: Commands,
+    interactors: Query<(Entity, &XrRayVisual), Without<RayVisualSpawned>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (interactor, visual) in interactors.iter() {
+        //the line and reticle share the material, so they change color together
+        let material = materials.add(StandardMaterial {
+            base_color: visual.idle_color,
+            unlit: true,
+            cull_mode: None,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        });
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(line_mesh(&[Vec3::ZERO, Vec3::NEG_Z], visual.width)),
+                material: material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            //the mesh is rebuilt in world space every frame, its bounds would be stale
+            NoFrustumCulling,
+            RayVisualPart {
+                interactor,
+                reticle: false,
+            },
+        ));
+        commands.spawn((
+            PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Torus {
+                    radius: 1.0,
+                    ring_radius: 0.15,
+                    subdivisions_segments: 24,
+                    subdivisions_sides: 8,
+                })),
+                material,
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            RayVisualPart {
+                interactor,
+                reticle: true,
+            },
+        ));
+        commands.entity(interactor).insert(RayVisualSpawned);
+    }
+}
+
+fn update_ray_visuals(
+    mut commands: Commands,
+    interactors: Query<(
+        &XrRayVisual,
+        &XRInteractorState,
+        Option<&XrRayPath>,
+        Option<&XrRayHit>,
+    )>,
+    mut parts: Query<(
+        Entity,
+        &RayVisualPart,
+        &Handle<Mesh>,
+        &Handle<StandardMaterial>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (entity, part, mesh, material, mut transform, mut visibility) in parts.iter_mut() {
+        let (visual, state, path, hit) = match interactors.get(part.interactor) {
+            Ok(interactor) => interactor,
+            //the interactor is gone or doesn't want a visual anymore
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                if let Some(mut interactor) = commands.get_entity(part.interactor) {
+                    interactor.remove::<RayVisualSpawned>();
+                }
+                continue;
+            }
+        };
+        let hit = hit.and_then(|hit| hit.0).map(|(_, hit)| hit);
+        let color = match (state, hit) {
+            (XRInteractorState::Selecting, _) => visual.select_color,
+            (XRInteractorState::Idle, Some(_)) => visual.hover_color,
+            (XRInteractorState::Idle, None) => visual.idle_color,
+        };
+        //get_mut marks the material as changed, so only use it when the color changes
+        let changed = materials
+            .get(material)
+            .map_or(false, |current| current.base_color != color);
+        if changed {
+            materials.get_mut(material).unwrap().base_color = color;
+        }
+        let visible = match part.reticle {
+            true => match hit {
+                Some(hit) if visual.reticle_radius > 0.0 => {
+                    //slightly in front of the surface so it doesn't z-fight with it
+                    *transform = Transform::from_translation(hit.point + hit.normal * 0.001)
+                        .with_rotation(Quat::from_rotation_arc(Vec3::Y, hit.normal))
+                        .with_scale(Vec3::splat(visual.reticle_radius));
+                    true
+                }
+                _ => false,
+            },
+            false => match path {
+                Some(path) if path.0.len() >= 2 => {
+                    if let Some(mesh) = meshes.get_mut(mesh) {
+                        *mesh = line_mesh(&path.0, visual.width);
+                    }
+                    true
+                }
+                _ => false,
+            },
+        };
+        let new_visibility = match visible {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+}
+
+// two ribbons crossing along the points, so the line has the same width from every side
+pub(crate) fn line_mesh(points: &[Vec3], width: f32) -> Mesh {
+    let half_width = width * 0.5;
+    let mut positions = Vec::with_capacity(points.len() * 4);
+    let mut normals = Vec::with_capacity(points.len() * 4);
+    for (i, point) in points.iter().enumerate() {
+        let previous = points[i.saturating_sub(1)];
+        let next = points[(i + 1).min(points.len() - 1)];
+        let tangent = (next - previous).normalize_or_zero();
+        let side = match tangent.cross(Vec3::Y).try_normalize() {
+            Some(side) => side,
+            None => tangent.any_orthonormal_vector(),
+        };
+        let up = side.cross(tangent);
+        for (offset, normal) in [(-side, up), (side, up), (-up, side), (up, side)] {
+            positions.push((*point + offset * half_width).to_array());
+            normals.push(normal.to_array());
+        }
+    }
+    let mut indices = Vec::with_capacity(points.len().saturating_sub(1) * 12);
+    for segment in 0..points.len().saturating_sub(1) as u32 {
+        let start = segment * 4;
+        let end = start + 4;
+        for ribbon in [0, 2] {
+            let (a, b) = (start + ribbon, start + ribbon + 1);
+            let (c, d) = (end + ribbon, end + ribbon + 1);
+            indices.extend_from_slice(&[a, b, c, b, d, c]);
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
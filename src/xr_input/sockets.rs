@@ -0,0 +1,300 @@
+use bevy::prelude::*;
+
+use super::grabbable::{XrAttachMode, XrGrabReleased, XrGrabState, XrGrabbable};
+use super::interactions::{XRInteractorState, XRSelection};
+
+/// Holds an [`XrGrabbable`] that is released close to it.
+///
+/// Sockets only hover interactables through the interaction systems, the socketed grabbable
+/// can be grabbed out of the socket by any other interactor. The [`XRInteractorState`] and
+/// [`XRSelection`] of the socket show what it holds, without sending select events.
+#[derive(Component, Clone, Debug)]
+pub struct XrSocket {
+    /// released grabbables closer than this in meters go into the socket
+    pub radius: f32,
+    /// seconds it takes to move a grabbable into the socket, 0 snaps it right away
+    pub tween_duration: f32,
+    /// only grabbables with this [`XrSocketTag`] fit, if set
+    pub accepts: Option<String>,
+    socketed: Option<Entity>,
+    // seconds since socketing and where the grabbable started from
+    tween: Option<(f32, Transform)>,
+}
+
+impl Default for XrSocket {
+    fn default() -> Self {
+        Self {
+            radius: 0.1,
+            tween_duration: 0.1,
+            accepts: None,
+            socketed: None,
+            tween: None,
+        }
+    }
+}
+
+impl XrSocket {
+    /// the grabbable in the socket
+    pub fn socketed(&self) -> Option<Entity> {
+        self.socketed
+    }
+
+    pub fn accepts(&self, tag: Option<&XrSocketTag>) -> bool {
+        match (&self.accepts, tag) {
+            (None, _) => true,
+            (Some(accepted), Some(tag)) => *accepted == tag.0,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Lets a grabbable into sockets that only accept this tag
+#[derive(Component, Clone, Debug, PartialEq, Eq)]
+pub struct XrSocketTag(pub String);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Socketed {
+    pub socket: Entity,
+    pub interactable: Entity,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Unsocketed {
+    pub socket: Entity,
+    pub interactable: Entity,
+}
+
+pub fn update_sockets(
+    time: Res<Time>,
+    mut released: EventReader<XrGrabReleased>,
+    mut sockets: Query<(
+        Entity,
+        &mut XrSocket,
+        &GlobalTransform,
+        Option<&mut XRInteractorState>,
+        Option<&mut XRSelection>,
+    )>,
+    mut grabbables: Query<
+        (
+            &XrGrabbable,
+            &XrGrabState,
+            &mut Transform,
+            &GlobalTransform,
+            Option<&XrSocketTag>,
+            Option<&Parent>,
+        ),
+        Without<XrSocket>,
+    >,
+    parents: Query<&GlobalTransform, (Without<XrSocket>, Without<XrGrabbable>)>,
+    mut socketed_events: EventWriter<Socketed>,
+    mut unsocketed_events: EventWriter<Unsocketed>,
+) {
+    for event in released.read() {
+        let (position, start, tag) = match grabbables.get(event.grabbable) {
+            Ok((_, _, _, transform, tag, _)) => {
+                (transform.translation(), transform.compute_transform(), tag)
+            }
+            Err(_) => continue,
+        };
+        //the closest free socket it fits into
+        let socket = sockets
+            .iter_mut()
+            .filter(|(_, socket, transform, _, _)| {
+                socket.socketed.is_none()
+                    && socket.accepts(tag)
+                    && transform.translation().distance(position) < socket.radius
+            })
+            .min_by(|(_, _, a, _, _), (_, _, b, _, _)| {
+                let a = a.translation().distance_squared(position);
+                let b = b.translation().distance_squared(position);
+                a.total_cmp(&b)
+            });
+        if let Some((socket_entity, mut socket, _, _, _)) = socket {
+            socket.socketed = Some(event.grabbable);
+            socket.tween = Some((0.0, start));
+            socketed_events.send(Socketed {
+                socket: socket_entity,
+                interactable: event.grabbable,
+            });
+        }
+    }
+
+    for (socket_entity, mut socket, socket_transform, state, selection) in sockets.iter_mut() {
+        let interactable = match socket.socketed {
+            Some(interactable) => interactable,
+            None => {
+                set_socket_state(state, selection, None);
+                continue;
+            }
+        };
+        let (grabbable, _, mut transform, _, _, parent) = match grabbables.get_mut(interactable) {
+            //grabbing it takes it out of the socket
+            Ok(grabbable) if !grabbable.1.is_grabbed() => grabbable,
+            _ => {
+                socket.socketed = None;
+                socket.tween = None;
+                unsocketed_events.send(Unsocketed {
+                    socket: socket_entity,
+                    interactable,
+                });
+                set_socket_state(state, selection, None);
+                continue;
+            }
+        };
+        set_socket_state(state, selection, Some(interactable));
+
+        let socket_transform = socket_transform.compute_transform();
+        let mut target = Transform::from_translation(socket_transform.translation)
+            .with_rotation(socket_transform.rotation);
+        if grabbable.attach_mode == XrAttachMode::Snap {
+            target = target.mul_transform(Transform::from_matrix(
+                grabbable.attach_point.compute_matrix().inverse(),
+            ));
+        }
+        let parent = parent.and_then(|parent| parents.get(parent.get()).ok());
+        let (elapsed, start) = match socket.tween {
+            Some(tween) => tween,
+            None => (0.0, target),
+        };
+        target.scale = start.scale;
+        let elapsed = elapsed + time.delta_seconds();
+        let t = match socket.tween_duration > 0.0 {
+            true => (elapsed / socket.tween_duration).min(1.0),
+            false => 1.0,
+        };
+        socket.tween = match t < 1.0 {
+            true => Some((elapsed, start)),
+            false => None,
+        };
+        //ease out so it settles into the socket
+        let t = 1.0 - (1.0 - t) * (1.0 - t);
+        let world = Transform {
+            translation: start.translation.lerp(target.translation, t),
+            rotation: start.rotation.slerp(target.rotation, t),
+            scale: start.scale,
+        };
+        let new_transform = match parent {
+            Some(parent) => GlobalTransform::from(world).reparented_to(parent),
+            None => world,
+        };
+        if *transform != new_transform {
+            *transform = new_transform;
+        }
+    }
+}
+
+fn set_socket_state(
+    state: Option<Mut<XRInteractorState>>,
+    selection: Option<Mut<XRSelection>>,
+    socketed: Option<Entity>,
+) {
+    let (new_state, new_selection) = match socketed {
+        Some(interactable) => (
+            XRInteractorState::Selecting,
+            XRSelection::Full(interactable),
+        ),
+        None => (XRInteractorState::Idle, XRSelection::Empty),
+    };
+    match state {
+        Some(mut state) if *state != new_state => *state = new_state,
+        _ => (),
+    }
+    match selection {
+        Some(mut selection) if *selection != new_selection => *selection = new_selection,
+        _ => (),
+    }
+}
+
+/// Shows where a held grabbable would go when it's released
+pub fn draw_socket_previews(
+    mut gizmos: Gizmos,
+    sockets: Query<(&XrSocket, &GlobalTransform)>,
+    grabbables: Query<(&XrGrabState, &GlobalTransform, Option<&XrSocketTag>), With<XrGrabbable>>,
+) {
+    for (state, transform, tag) in grabbables.iter() {
+        if !state.is_grabbed() {
+            continue;
+        }
+        let position = transform.translation();
+        let socket = sockets
+            .iter()
+            .filter(|(socket, socket_transform)| {
+                socket.socketed.is_none()
+                    && socket.accepts(tag)
+                    && socket_transform.translation().distance(position) < socket.radius
+            })
+            .map(|(_, socket_transform)| socket_transform.compute_transform())
+            .min_by(|a, b| {
+                let a = a.translation.distance_squared(position);
+                let b = b.translation.distance_squared(position);
+                a.total_cmp(&b)
+            });
+        if let Some(socket) = socket {
+            let preview = Transform::from_translation(socket.translation)
+                .with_rotation(socket.rotation)
+                .with_scale(Vec3::splat(0.12));
+            gizmos.cuboid(preview, Color::CYAN);
+            gizmos.line(position, socket.translation, Color::CYAN);
+        }
+    }
+}
+
+#[cfg(feature = "rapier")]
+pub use self::rapier::*;
+
+#[cfg(feature = "rapier")]
+mod rapier {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::{RigidBody, Velocity};
+
+    use super::{Socketed, Unsocketed};
+    use crate::xr_input::grabbable::{XrGrabState, XrGrabbedRigidBody};
+
+    /// The body type a grabbable had before it went into a socket
+    #[derive(Component, Clone, Copy, Debug)]
+    pub struct XrSocketedRigidBody(pub RigidBody);
+
+    /// Keeps rapier bodies kinematic while they are in a socket
+    pub fn apply_sockets_to_rapier_bodies(
+        mut commands: Commands,
+        mut socketed: EventReader<Socketed>,
+        mut unsocketed: EventReader<Unsocketed>,
+        mut bodies: Query<(
+            &mut RigidBody,
+            &XrGrabState,
+            Option<&mut Velocity>,
+            Option<&XrSocketedRigidBody>,
+        )>,
+    ) {
+        for event in socketed.read() {
+            if let Ok((mut body, _, velocity, None)) = bodies.get_mut(event.interactable) {
+                commands
+                    .entity(event.interactable)
+                    .insert(XrSocketedRigidBody(*body));
+                *body = RigidBody::KinematicPositionBased;
+                if let Some(mut velocity) = velocity {
+                    *velocity = Velocity::zero();
+                }
+            }
+        }
+        for event in unsocketed.read() {
+            let (mut body, state, _, socketed_body) = match bodies.get_mut(event.interactable) {
+                Ok(body) => body,
+                Err(_) => continue,
+            };
+            let socketed_body = match socketed_body {
+                Some(socketed_body) => socketed_body.0,
+                None => continue,
+            };
+            let mut entity = commands.entity(event.interactable);
+            entity.remove::<XrSocketedRigidBody>();
+            match state.is_grabbed() {
+                //grabbing remembered the kinematic body from the socket, release restores this
+                true => {
+                    entity.insert(XrGrabbedRigidBody(socketed_body));
+                }
+                false => *body = socketed_body,
+            }
+        }
+    }
+}
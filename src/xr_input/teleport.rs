@@ -0,0 +1,492 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::render::view::NoFrustumCulling;
+
+use crate::{
+    input::XrInput,
+    resources::{XrFrameState, XrInstance, XrSession},
+};
+
+use super::{
+    interactions::{cut_path, XRRayInteractor, XrInteractableShape, XrInteractionHit, XrRayCurve},
+    locomotion::{
+        flat_yaw, XrLocomotionKind, XrLocomotionMove, XrLocomotionMoves, XrLocomotionSet,
+    },
+    oculus_touch::OculusController,
+    ray_visuals::line_mesh,
+    trackers::{head_transform, OpenXREyes, OpenXRLeftEye, OpenXRTrackingRoot},
+    Hand, QuatConv, Vec3Conv,
+};
+
+/// Teleports the player to [`TeleportArea`]s and [`TeleportAnchor`]s.
+///
+/// Pushing the thumbstick forward shows an arc from the controller, letting go of the stick
+/// teleports to where it lands. The direction of the stick while aiming turns the player.
+///
+/// With the [`XrLocomotionPlugin`](super::locomotion::XrLocomotionPlugin) the teleport is an
+/// [`XrLocomotionMove`] like any other and can be vetoed.
+#[derive(Default)]
+pub struct XrTeleportPlugin;
+
+impl Plugin for XrTeleportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeleportLocomotion>()
+            .init_resource::<TeleportState>()
+            .add_systems(Startup, spawn_teleport_visuals)
+            .add_systems(
+                Update,
+                (
+                    update_teleport_aim,
+                    update_teleport_fade,
+                    update_teleport_visuals,
+                )
+                    .chain()
+                    //locomotion shouldn't turn with the stick the teleport started aiming with
+                    .before(XrLocomotionSet::Input),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct TeleportLocomotion {
+    /// the controller that aims
+    pub hand: Hand,
+    /// the shape and length of the arc, usually a projectile curve
+    pub arc: XRRayInteractor,
+    /// pushing the thumbstick forward past this starts aiming
+    pub activation_threshold: f32,
+    /// letting the thumbstick go below this teleports
+    pub release_threshold: f32,
+    /// whether the direction of the thumbstick turns the player while aiming
+    pub rotate_with_thumbstick: bool,
+    /// surfaces steeper than this in radians aren't valid destinations
+    pub max_slope: f32,
+    /// seconds it takes to fade to black and back again each, 0 teleports without fading
+    pub fade_duration: f32,
+    pub valid_color: Color,
+    pub invalid_color: Color,
+}
+
+impl Default for TeleportLocomotion {
+    fn default() -> Self {
+        Self {
+            hand: Hand::Right,
+            arc: XRRayInteractor {
+                max_length: 15.0,
+                curve: XrRayCurve::Projectile {
+                    speed: 7.0,
+                    gravity: 9.81,
+                },
+            },
+            activation_threshold: 0.7,
+            release_threshold: 0.3,
+            rotate_with_thumbstick: true,
+            max_slope: PI / 4.0,
+            fade_duration: 0.15,
+            valid_color: Color::CYAN,
+            invalid_color: Color::RED,
+        }
+    }
+}
+
+/// Anywhere on this is a valid destination.
+///
+/// The arc hits the [`XrInteractableShape`] of the entity, without one it hits the bounds of
+/// its mesh like [`XrInteractableShape::MeshAabb`], which is a 0.1 m sphere without a mesh.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct TeleportArea;
+
+/// A fixed destination, landing anywhere on its [`XrInteractableShape`] or mesh teleports onto
+/// the origin of the anchor
+#[derive(Component, Clone, Copy, Debug)]
+pub struct TeleportAnchor {
+    /// turns the player to the forward direction of the anchor instead of the aimed one
+    pub face_forward: bool,
+}
+
+impl Default for TeleportAnchor {
+    fn default() -> Self {
+        Self { face_forward: true }
+    }
+}
+
+/// Where a teleport goes, the head of the player ends up above `position`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TeleportTarget {
+    pub position: Vec3,
+    /// rotation around the y axis the head faces after the teleport
+    pub yaw: f32,
+    pub anchor: Option<Entity>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TeleportFade {
+    target: TeleportTarget,
+    elapsed: f32,
+    teleported: bool,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct TeleportState {
+    aiming: bool,
+    stick_yaw: f32,
+    path: Vec<Vec3>,
+    target: Option<TeleportTarget>,
+    fade: Option<TeleportFade>,
+}
+
+impl TeleportState {
+    pub fn is_aiming(&self) -> bool {
+        self.aiming
+    }
+
+    /// the arc in world space, it ends where it hits something
+    pub fn path(&self) -> &[Vec3] {
+        &self.path
+    }
+
+    /// where releasing the thumbstick would teleport to
+    pub fn target(&self) -> Option<TeleportTarget> {
+        self.target
+    }
+
+    /// whether a teleport is fading right now
+    pub fn is_teleporting(&self) -> bool {
+        self.fade.is_some()
+    }
+}
+
+// the entities showing the arc, the target and the fade
+#[derive(Resource)]
+struct TeleportVisuals {
+    arc: Entity,
+    arc_mesh: Handle<Mesh>,
+    reticle: Entity,
+    material: Handle<StandardMaterial>,
+    fade: Entity,
+    fade_material: Handle<StandardMaterial>,
+}
+
+fn yaw_of(forward: Vec3) -> f32 {
+    f32::atan2(-forward.x, -forward.z)
+}
+
+pub fn update_teleport_aim(
+    config: Res<TeleportLocomotion>,
+    mut state: ResMut<TeleportState>,
+    oculus_controller: Res<OculusController>,
+    frame_state: Res<XrFrameState>,
+    xr_input: Res<XrInput>,
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    tracking_root: Query<&Transform, With<OpenXRTrackingRoot>>,
+    destinations: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Option<&XrInteractableShape>,
+            Option<&Aabb>,
+            Option<&TeleportAnchor>,
+        ),
+        Or<(With<TeleportArea>, With<TeleportAnchor>)>,
+    >,
+) {
+    //no aiming while the last teleport is still fading
+    if state.fade.is_some() {
+        return;
+    }
+    //get frame state
+    let frame_state = **frame_state;
+    //get controller
+    let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
+    let thumbstick = controller.thumbstick(config.hand);
+    let stick = Vec2::new(thumbstick.x, thumbstick.y);
+    let was_aiming = state.aiming;
+    state.aiming = match was_aiming {
+        true => stick.length() >= config.release_threshold,
+        false => stick.y >= config.activation_threshold,
+    };
+    match (was_aiming, state.aiming) {
+        (true, false) => {
+            //released, teleport if the arc landed somewhere valid
+            state.fade = state.target.take().map(|target| TeleportFade {
+                target,
+                elapsed: 0.0,
+                teleported: false,
+            });
+            state.path.clear();
+            return;
+        }
+        (false, false) => return,
+        (false, true) => state.stick_yaw = 0.0,
+        (true, true) => (),
+    }
+    //only use the stick direction while it's pushed far, it swings around when let go of
+    if config.rotate_with_thumbstick && stick.length() >= config.activation_threshold {
+        state.stick_yaw = f32::atan2(-stick.x, stick.y);
+    }
+
+    let root = match tracking_root.get_single() {
+        Ok(root) => *root,
+        Err(_) => return,
+    };
+    let aim = controller.aim_space(config.hand).0.pose;
+    let origin = root.transform_point(aim.position.to_vec3());
+    let direction = root.rotation * (aim.orientation.to_quat() * Vec3::NEG_Z);
+    let path = config.arc.path(origin, direction);
+
+    let mut closest: Option<(
+        Entity,
+        &GlobalTransform,
+        Option<&TeleportAnchor>,
+        XrInteractionHit,
+    )> = None;
+    for (entity, transform, shape, aabb, anchor) in destinations.iter() {
+        let hit = match shape
+            .copied()
+            .unwrap_or(XrInteractableShape::MeshAabb)
+            .raycast_path(transform, aabb, &path)
+        {
+            Some(hit) => hit,
+            None => continue,
+        };
+        let closer = closest.map_or(true, |(_, _, _, closest)| hit.distance < closest.distance);
+        if closer {
+            closest = Some((entity, transform, anchor, hit));
+        }
+    }
+    let aim_yaw = yaw_of(direction) + state.stick_yaw;
+    state.target = match closest {
+        Some((entity, transform, Some(anchor), _)) => Some(TeleportTarget {
+            position: transform.translation(),
+            yaw: match anchor.face_forward {
+                true => yaw_of(transform.forward()),
+                false => aim_yaw,
+            },
+            anchor: Some(entity),
+        }),
+        Some((_, _, None, hit)) if hit.normal.y >= config.max_slope.cos() => Some(TeleportTarget {
+            position: hit.point,
+            yaw: aim_yaw,
+            anchor: None,
+        }),
+        _ => None,
+    };
+    state.path = match closest {
+        Some((_, _, _, hit)) => cut_path(&path, hit.distance),
+        None => path,
+    };
+}
+
+/// Fades to black, teleports and fades back in
+pub fn update_teleport_fade(
+    time: Res<Time>,
+    config: Res<TeleportLocomotion>,
+    mut state: ResMut<TeleportState>,
+    moves: Option<ResMut<XrLocomotionMoves>>,
+    mut tracking_root: Query<&mut Transform, With<OpenXRTrackingRoot>>,
+    eyes: Query<&GlobalTransform, OpenXREyes>,
+) {
+    let mut fade = match state.fade {
+        Some(fade) => fade,
+        None => return,
+    };
+    fade.elapsed += time.delta_seconds();
+    if !fade.teleported && fade.elapsed >= config.fade_duration {
+        let head = head_transform(&eyes);
+        match (tracking_root.get_single_mut(), head, moves) {
+            (Ok(root), Some(head), Some(mut moves)) => {
+                moves.push(teleport_move(&root, head, &fade.target))
+            }
+            (Ok(mut root), Some(head), None) => {
+                teleport_move(&root, head, &fade.target).apply(&mut root)
+            }
+            _ => (),
+        }
+        fade.teleported = true;
+    }
+    state.fade = match fade.teleported && fade.elapsed >= config.fade_duration * 2.0 {
+        true => None,
+        false => Some(fade),
+    };
+}
+
+// turns around the head and moves it above the target, facing its yaw
+fn teleport_move(root: &Transform, head: Transform, target: &TeleportTarget) -> XrLocomotionMove {
+    //the floor of the tracking space lands on the target
+    let translation = Vec3::new(
+        target.position.x - head.translation.x,
+        target.position.y - root.translation.y,
+        target.position.z - head.translation.z,
+    );
+    XrLocomotionMove::new(
+        XrLocomotionKind::Teleport,
+        translation,
+        target.yaw - flat_yaw(head.rotation),
+        head.translation,
+    )
+}
+
+fn spawn_teleport_visuals(
+    mut commands: Commands,
+    config: Res<TeleportLocomotion>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = materials.add(StandardMaterial {
+        base_color: config.valid_color,
+        unlit: true,
+        cull_mode: None,
+        ..default()
+    });
+    let arc_mesh = meshes.add(line_mesh(&[Vec3::ZERO, Vec3::NEG_Z], 0.01));
+    let arc = commands
+        .spawn((
+            PbrBundle {
+                mesh: arc_mesh.clone(),
+                material: material.clone(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            NoFrustumCulling,
+        ))
+        .id();
+    //a ring with an arrow pointing where the player will face
+    let reticle = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Torus {
+                radius: 0.25,
+                ring_radius: 0.015,
+                subdivisions_segments: 32,
+                subdivisions_sides: 8,
+            })),
+            material: material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .with_children(|reticle| {
+            reticle.spawn(PbrBundle {
+                mesh: meshes.add(Mesh::from(shape::Box::new(0.04, 0.01, 0.2))),
+                material: material.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, -0.35),
+                ..default()
+            });
+        })
+        .id();
+    //a sphere around the head, seen from the inside, it moves with the left eye once there is one
+    //and is big enough for the right eye to be inside as well
+    let fade_material = materials.add(StandardMaterial {
+        base_color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+        unlit: true,
+        cull_mode: None,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    let fade = commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::UVSphere {
+                radius: 0.3,
+                sectors: 16,
+                stacks: 8,
+            })),
+            material: fade_material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .id();
+    commands.insert_resource(TeleportVisuals {
+        arc,
+        arc_mesh,
+        reticle,
+        material,
+        fade,
+        fade_material,
+    });
+}
+
+fn update_teleport_visuals(
+    mut commands: Commands,
+    config: Res<TeleportLocomotion>,
+    state: Res<TeleportState>,
+    visuals: Option<Res<TeleportVisuals>>,
+    left_eye: Query<Entity, With<OpenXRLeftEye>>,
+    unparented: Query<(), Without<Parent>>,
+    mut entities: Query<(&mut Transform, &mut Visibility)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let visuals = match visuals {
+        Some(visuals) => visuals,
+        None => return,
+    };
+    let color = match state.target {
+        Some(_) => config.valid_color,
+        None => config.invalid_color,
+    };
+    set_color(&mut materials, &visuals.material, color);
+
+    let show_arc = state.path.len() >= 2;
+    if show_arc {
+        if let Some(mesh) = meshes.get_mut(&visuals.arc_mesh) {
+            *mesh = line_mesh(&state.path, 0.01);
+        }
+    }
+    set_visible(&mut entities, visuals.arc, show_arc);
+
+    if let (Some(target), Ok((mut transform, _))) =
+        (state.target, entities.get_mut(visuals.reticle))
+    {
+        *transform = Transform::from_translation(target.position + Vec3::Y * 0.005)
+            .with_rotation(Quat::from_rotation_y(target.yaw));
+    }
+    set_visible(&mut entities, visuals.reticle, state.target.is_some());
+
+    //black at the moment of the teleport, clear at the start and the end of the fade
+    let alpha = match state.fade {
+        Some(fade) if config.fade_duration > 0.0 => {
+            1.0 - ((fade.elapsed - config.fade_duration).abs() / config.fade_duration).min(1.0)
+        }
+        _ => 0.0,
+    };
+    set_color(
+        &mut materials,
+        &visuals.fade_material,
+        Color::rgba(0.0, 0.0, 0.0, alpha),
+    );
+    //a child of the eye follows this frame's teleport, copying the eye's position would lag
+    if let (true, Ok(eye)) = (unparented.contains(visuals.fade), left_eye.get_single()) {
+        commands.entity(eye).add_child(visuals.fade);
+    }
+    set_visible(&mut entities, visuals.fade, alpha > 0.0);
+}
+
+fn set_color(
+    materials: &mut Assets<StandardMaterial>,
+    material: &Handle<StandardMaterial>,
+    color: Color,
+) {
+    //get_mut marks the material as changed, so only use it when the color changes
+    let changed = materials
+        .get(material)
+        .map_or(false, |current| current.base_color != color);
+    if changed {
+        materials.get_mut(material).unwrap().base_color = color;
+    }
+}
+
+fn set_visible(
+    entities: &mut Query<(&mut Transform, &mut Visibility)>,
+    entity: Entity,
+    visible: bool,
+) {
+    let new_visibility = match visible {
+        true => Visibility::Inherited,
+        false => Visibility::Hidden,
+    };
+    match entities.get_mut(entity) {
+        Ok((_, mut visibility)) if *visibility != new_visibility => *visibility = new_visibility,
+        _ => (),
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::{
-    info, Added, BuildChildren, Commands, Component, Entity, Query, Res, Transform, Vec3, With,
-    Without,
+    info, Added, BuildChildren, Commands, Component, Entity, GlobalTransform, Or, Query, Res,
+    Transform, Vec3, With, Without,
 };
 
 use crate::{
@@ -26,9 +26,22 @@ pub struct OpenXRLeftController;
 pub struct OpenXRRightController;
 #[derive(Component)]
 pub struct OpenXRController;
-#[derive(Component)]
+#[derive(Component, Default)]
 pub struct AimPose(pub Transform);
 
+/// query filter for both eyes, see [`head_transform`]
+pub type OpenXREyes = Or<(With<OpenXRLeftEye>, With<OpenXRRightEye>)>;
+
+/// The head in world space, halfway between the eyes and facing where they face
+pub fn head_transform(eyes: &Query<&GlobalTransform, OpenXREyes>) -> Option<Transform> {
+    eyes.iter()
+        .map(|eye| eye.compute_transform())
+        .reduce(|left, right| Transform {
+            translation: left.translation.lerp(right.translation, 0.5),
+            ..left
+        })
+}
+
 pub fn adopt_open_xr_trackers(
     query: Query<Entity, Added<OpenXRTracker>>,
     mut commands: Commands,
@@ -67,7 +80,7 @@ pub fn update_open_xr_controllers(
     session: Res<XrSession>,
 ) {
     //lock dat frame?
-    let frame_state = *frame_state.lock().unwrap();
+    let frame_state = **frame_state;
     //get controller
     let controller = oculus_controller.get_ref(&instance, &session, &frame_state, &xr_input);
     //get left controller
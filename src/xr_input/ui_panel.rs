@@ -0,0 +1,505 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::ui::{CalculatedClip, FocusPolicy, UiStack, UiSystem};
+use bevy::window::PrimaryWindow;
+
+use crate::{
+    input::XrInput,
+    resources::{XrFrameState, XrInstance, XrSession},
+};
+
+use super::{
+    interactions::{
+        InteractionEvent, XRSelection, XrHoverTarget, XrInteractableBundle, XrInteractableShape,
+        XrInteractionSet, XrSelectMode,
+    },
+    oculus_touch::OculusController,
+    xr_camera::XRProjection,
+    Hand,
+};
+
+/// Shows the bevy_ui tree on [`XrUiPanel`]s in the world and lets [`XrUiPointer`]s use it.
+///
+/// bevy_ui lays out a single tree for the primary window, so every panel shows the whole UI
+/// at the logical size of that window. While there are panels the pointers drive the
+/// [`Interaction`]s instead of the mouse. The XR cameras never draw the UI on top of the view,
+/// it only shows on the panels.
+#[derive(Default)]
+pub struct XrUiPanelPlugin;
+
+impl Plugin for XrUiPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<XrUiPointerEvent>()
+            .configure_sets(PreUpdate, UiSystem::Focus.run_if(no_ui_panels))
+            .add_systems(PreUpdate, route_ui_pointers.after(UiSystem::Focus))
+            .add_systems(
+                Update,
+                (
+                    (remove_ui_panels, update_ui_panels, hide_ui_from_xr_cameras)
+                        .before(XrInteractionSet::Detect),
+                    update_ui_pointers.in_set(XrInteractionSet::Feedback),
+                ),
+            );
+    }
+}
+
+/// A flat panel showing the UI on its front, which is the local +Z side.
+///
+/// The panel becomes an interactable, its [`XrInteractableShape`] follows the size of the UI.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct XrUiPanel {
+    /// width in meters, the height follows the aspect of the UI
+    pub width: f32,
+}
+
+impl Default for XrUiPanel {
+    fn default() -> Self {
+        Self { width: 1.0 }
+    }
+}
+
+// what was created for a panel
+#[derive(Component)]
+struct UiPanelTexture {
+    camera: Entity,
+    quad: Entity,
+    image: Handle<Image>,
+    mesh: Handle<Mesh>,
+    // logical size of the UI the texture was made for
+    size: UVec2,
+    width: f32,
+}
+
+// the camera and quad of a panel, so they can be found once the panel is despawned
+#[derive(Component)]
+struct UiPanelPart {
+    panel: Entity,
+}
+
+/// Lets an interactor point at and press the UI on [`XrUiPanel`]s.
+///
+/// Put it on ray or poke interactors, their hover target and selection decide where they point
+/// and whether they press.
+#[derive(Component, Clone, Debug)]
+pub struct XrUiPointer {
+    /// the thumbstick of this hand scrolls whatever the pointer is on
+    pub scroll_hand: Option<Hand>,
+    /// logical pixels per second at full thumbstick deflection
+    pub scroll_speed: f32,
+    // where on the UI it points in logical pixels, whether it presses and how far to scroll
+    position: Option<Vec2>,
+    pressing: bool,
+    scroll: Vec2,
+    // what routing saw last, the nodes are from top to bottom, a press begun on empty space
+    // holds no nodes
+    last_position: Option<Vec2>,
+    hovered: Vec<Entity>,
+    pressed: Vec<Entity>,
+    was_pressing: bool,
+}
+
+impl Default for XrUiPointer {
+    fn default() -> Self {
+        Self {
+            scroll_hand: None,
+            scroll_speed: 600.0,
+            position: None,
+            pressing: false,
+            scroll: Vec2::ZERO,
+            last_position: None,
+            hovered: Vec::new(),
+            pressed: Vec::new(),
+            was_pressing: false,
+        }
+    }
+}
+
+impl XrUiPointer {
+    pub fn with_scroll_hand(mut self, hand: Hand) -> Self {
+        self.scroll_hand = Some(hand);
+        self
+    }
+
+    /// where the pointer is on the UI in logical pixels
+    pub fn position(&self) -> Option<Vec2> {
+        self.position
+    }
+
+    /// the UI nodes under the pointer from top to bottom
+    pub fn hovered(&self) -> &[Entity] {
+        &self.hovered
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum XrUiPointerEventKind {
+    Enter,
+    Exit,
+    Press,
+    /// sent to the nodes the press started on, wherever the pointer is now
+    Release,
+    /// a press and release on the same node
+    Click,
+    /// how far the pointer moved while pressing, in logical pixels
+    Drag(Vec2),
+    /// how far to scroll in logical pixels, positive y scrolls down
+    Scroll(Vec2),
+}
+
+/// What an [`XrUiPointer`] did to a UI node, for everything [`Interaction`] can't express
+#[derive(Event, Clone, Copy, Debug)]
+pub struct XrUiPointerEvent {
+    pub pointer: Entity,
+    pub node: Entity,
+    pub kind: XrUiPointerEventKind,
+    /// where the pointer is on the UI in logical pixels
+    pub position: Vec2,
+}
+
+fn no_ui_panels(panels: Query<(), With<XrUiPanel>>) -> bool {
+    panels.is_empty()
+}
+
+// the logical size bevy_ui lays the tree out for
+fn ui_size(window: Option<&Window>) -> UVec2 {
+    match window {
+        Some(window) => UVec2::new(window.width() as u32, window.height() as u32).max(UVec2::ONE),
+        None => UVec2::new(1280, 720),
+    }
+}
+
+pub fn update_ui_panels(
+    mut commands: Commands,
+    mut panels: Query<(Entity, &XrUiPanel, Option<&mut UiPanelTexture>)>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = ui_size(primary_window.get_single().ok());
+    let extent = Extent3d {
+        width: size.x,
+        height: size.y,
+        ..default()
+    };
+    for (entity, panel, texture) in panels.iter_mut() {
+        let panel_size = Vec2::new(panel.width, panel.width * size.y as f32 / size.x as f32);
+        let interactable_shape = XrInteractableShape::Box {
+            half_extents: (panel_size * 0.5).extend(0.005),
+        };
+        match texture {
+            Some(mut texture) => {
+                if texture.size == size && texture.width == panel.width {
+                    continue;
+                }
+                //the window was resized, the UI follows it
+                if let Some(image) = images.get_mut(&texture.image) {
+                    image.resize(extent);
+                }
+                if let Some(mesh) = meshes.get_mut(&texture.mesh) {
+                    *mesh = Mesh::from(shape::Quad::new(panel_size));
+                }
+                texture.size = size;
+                texture.width = panel.width;
+                commands.entity(entity).insert(interactable_shape);
+            }
+            None => {
+                let mut image = Image {
+                    texture_descriptor: TextureDescriptor {
+                        label: Some("xr ui panel"),
+                        size: extent,
+                        dimension: TextureDimension::D2,
+                        format: TextureFormat::Bgra8UnormSrgb,
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        usage: TextureUsages::TEXTURE_BINDING
+                            | TextureUsages::COPY_DST
+                            | TextureUsages::RENDER_ATTACHMENT,
+                        view_formats: &[],
+                    },
+                    ..default()
+                };
+                image.resize(extent);
+                let image = images.add(image);
+                let mesh = meshes.add(Mesh::from(shape::Quad::new(panel_size)));
+                let camera = commands
+                    .spawn((
+                        Camera2dBundle {
+                            camera: Camera {
+                                target: RenderTarget::Image(image.clone()),
+                                order: -1,
+                                ..default()
+                            },
+                            camera_2d: Camera2d {
+                                clear_color: ClearColorConfig::Custom(Color::NONE),
+                            },
+                            ..default()
+                        },
+                        UiPanelPart { panel: entity },
+                    ))
+                    .id();
+                let quad = commands
+                    .spawn((
+                        PbrBundle {
+                            mesh: mesh.clone(),
+                            material: materials.add(StandardMaterial {
+                                base_color_texture: Some(image.clone()),
+                                unlit: true,
+                                alpha_mode: AlphaMode::Blend,
+                                ..default()
+                            }),
+                            ..default()
+                        },
+                        UiPanelPart { panel: entity },
+                    ))
+                    .id();
+                commands
+                    .entity(entity)
+                    .insert((
+                        UiPanelTexture {
+                            camera,
+                            quad,
+                            image,
+                            mesh,
+                            size,
+                            width: panel.width,
+                        },
+                        XrInteractableBundle::default(),
+                        interactable_shape,
+                        //every pointer can press the panel
+                        XrSelectMode::Multiple,
+                    ))
+                    .add_child(quad);
+            }
+        }
+    }
+}
+
+/// Despawns the cameras and quads of panels that were despawned or lost their [`XrUiPanel`]
+fn remove_ui_panels(
+    mut commands: Commands,
+    mut removed: RemovedComponents<XrUiPanel>,
+    textures: Query<&UiPanelTexture, Without<XrUiPanel>>,
+    parts: Query<(Entity, &UiPanelPart)>,
+) {
+    for panel in removed.read() {
+        match textures.get(panel) {
+            Ok(texture) => {
+                commands.entity(texture.camera).despawn();
+                commands.entity(texture.quad).despawn_recursive();
+                commands.entity(panel).remove::<(
+                    UiPanelTexture,
+                    XrInteractableBundle,
+                    XrInteractableShape,
+                    XrSelectMode,
+                )>();
+            }
+            //the panel is gone with its texture, only the parts know they belonged to it
+            Err(_) => {
+                for (part, part_of) in parts.iter() {
+                    if part_of.panel == panel {
+                        commands.entity(part).despawn_recursive();
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn hide_ui_from_xr_cameras(
+    mut commands: Commands,
+    cameras: Query<Entity, (With<XRProjection>, Without<UiCameraConfig>)>,
+) {
+    for camera in cameras.iter() {
+        commands
+            .entity(camera)
+            .insert(UiCameraConfig { show_ui: false });
+    }
+}
+
+/// Finds where the pointers are on the panels from this frame's interactions
+pub fn update_ui_pointers(
+    mut events: EventReader<InteractionEvent>,
+    panels: Query<(&XrUiPanel, &UiPanelTexture, &GlobalTransform)>,
+    ui_scale: Res<UiScale>,
+    time: Res<Time>,
+    oculus_controller: Option<Res<OculusController>>,
+    frame_state: Res<XrFrameState>,
+    xr_input: Res<XrInput>,
+    instance: Res<XrInstance>,
+    session: Res<XrSession>,
+    mut pointers: Query<(Entity, &mut XrUiPointer, &XRSelection, &XrHoverTarget)>,
+) {
+    let mut hits = HashMap::new();
+    for event in events.read() {
+        if let (true, Some(hit)) = (panels.contains(event.interactable), event.hit) {
+            hits.insert((event.interactor, event.interactable), hit.point);
+        }
+    }
+    let controller = oculus_controller
+        .as_ref()
+        .map(|controller| controller.get_ref(&instance, &session, &frame_state, &xr_input));
+    for (entity, mut pointer, selection, hover) in pointers.iter_mut() {
+        //a press keeps going on the panel it started on
+        let (panel_entity, pressing) = match (*selection, hover.0) {
+            (XRSelection::Full(selected), _) if panels.contains(selected) => (Some(selected), true),
+            (_, Some(hovered)) if panels.contains(hovered) => (Some(hovered), false),
+            _ => (None, false),
+        };
+        let position = panel_entity.and_then(|panel_entity| {
+            let (panel, texture, transform) = panels.get(panel_entity).ok()?;
+            let point = hits.get(&(entity, panel_entity))?;
+            let local = transform.affine().inverse().transform_point3(*point);
+            let height = panel.width * texture.size.y as f32 / texture.size.x as f32;
+            //the UI starts in the top left corner
+            let uv = Vec2::new(local.x / panel.width + 0.5, 0.5 - local.y / height);
+            Some(uv * texture.size.as_vec2() / ui_scale.0 as f32)
+        });
+        pointer.position = match (position, pressing) {
+            (Some(position), _) => Some(position),
+            //dragging past the edge of the panel
+            (None, true) => pointer.position,
+            (None, false) => None,
+        };
+        pointer.pressing = pressing;
+        pointer.scroll = match (&controller, pointer.scroll_hand) {
+            (Some(controller), Some(hand)) => {
+                let thumbstick = controller.thumbstick(hand);
+                //pushing the stick forward scrolls up
+                Vec2::new(thumbstick.x, -thumbstick.y) * pointer.scroll_speed * time.delta_seconds()
+            }
+            _ => Vec2::ZERO,
+        };
+    }
+}
+
+/// Sets the [`Interaction`]s of the UI nodes under the pointers and sends the
+/// [`XrUiPointerEvent`]s, takes the place of bevy's focus system while there are panels
+pub fn route_ui_pointers(
+    panels: Query<(), With<XrUiPanel>>,
+    ui_stack: Res<UiStack>,
+    mut pointers: Query<(Entity, &mut XrUiPointer)>,
+    mut nodes: Query<(
+        &Node,
+        &GlobalTransform,
+        Option<&mut Interaction>,
+        Option<&FocusPolicy>,
+        Option<&ViewVisibility>,
+        Option<&CalculatedClip>,
+    )>,
+    mut events: EventWriter<XrUiPointerEvent>,
+) {
+    if panels.is_empty() {
+        return;
+    }
+    let mut hovered_nodes = HashSet::new();
+    let mut pressed_nodes = HashSet::new();
+    for (pointer_entity, mut pointer) in pointers.iter_mut() {
+        //the nodes under the pointer from top to bottom, until one blocks the ones below it
+        let mut hovered = Vec::new();
+        if let Some(position) = pointer.position {
+            for entity in ui_stack.uinodes.iter().rev() {
+                let (node, transform, _, focus_policy, visibility, clip) = match nodes.get(*entity)
+                {
+                    Ok(node) => node,
+                    Err(_) => continue,
+                };
+                if visibility.map_or(false, |visibility| !visibility.get()) {
+                    continue;
+                }
+                let rect = node.logical_rect(transform);
+                let visible_rect = clip.map_or(rect, |clip| rect.intersect(clip.clip));
+                if !visible_rect.contains(position) {
+                    continue;
+                }
+                hovered.push(*entity);
+                if *focus_policy.unwrap_or(&FocusPolicy::Block) == FocusPolicy::Block {
+                    break;
+                }
+            }
+        }
+
+        let position = pointer.position.or(pointer.last_position);
+        let mut send = |node: Entity, kind: XrUiPointerEventKind| {
+            if let Some(position) = position {
+                events.send(XrUiPointerEvent {
+                    pointer: pointer_entity,
+                    node,
+                    kind,
+                    position,
+                });
+            }
+        };
+        for node in pointer.hovered.iter() {
+            if !hovered.contains(node) {
+                send(*node, XrUiPointerEventKind::Exit);
+            }
+        }
+        for node in hovered.iter() {
+            if !pointer.hovered.contains(node) {
+                send(*node, XrUiPointerEventKind::Enter);
+            }
+        }
+        let is_pressing = pointer.pressing && pointer.position.is_some();
+        match (pointer.was_pressing, is_pressing) {
+            (false, true) => {
+                for node in hovered.iter() {
+                    send(*node, XrUiPointerEventKind::Press);
+                }
+                pointer.pressed = hovered.clone();
+            }
+            (true, false) => {
+                for node in pointer.pressed.iter() {
+                    send(*node, XrUiPointerEventKind::Release);
+                    if hovered.contains(node) {
+                        send(*node, XrUiPointerEventKind::Click);
+                    }
+                }
+                pointer.pressed.clear();
+            }
+            (true, true) => match (pointer.last_position, pointer.position) {
+                (Some(last), Some(current)) if last != current => {
+                    for node in pointer.pressed.iter() {
+                        send(*node, XrUiPointerEventKind::Drag(current - last));
+                    }
+                }
+                _ => (),
+            },
+            (false, false) => (),
+        }
+        if pointer.scroll != Vec2::ZERO {
+            for node in hovered.iter() {
+                send(*node, XrUiPointerEventKind::Scroll(pointer.scroll));
+            }
+        }
+
+        hovered_nodes.extend(hovered.iter().copied());
+        pressed_nodes.extend(pointer.pressed.iter().copied());
+        pointer.hovered = hovered;
+        pointer.last_position = pointer.position;
+        pointer.was_pressing = is_pressing;
+    }
+
+    for entity in ui_stack.uinodes.iter() {
+        let mut interaction = match nodes.get_mut(*entity) {
+            Ok((_, _, Some(interaction), _, _, _)) => interaction,
+            _ => continue,
+        };
+        let new_interaction = match (
+            pressed_nodes.contains(entity),
+            hovered_nodes.contains(entity),
+        ) {
+            (true, _) => Interaction::Pressed,
+            (false, true) => Interaction::Hovered,
+            (false, false) => Interaction::None,
+        };
+        //only on change so `Changed<Interaction>` works like with the mouse
+        interaction.set_if_neq(new_interaction);
+    }
+}
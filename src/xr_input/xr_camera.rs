@@ -240,7 +240,7 @@ impl CameraProjection for XRProjection {
 }
 
 pub fn xr_camera_head_sync(
-    views: ResMut<crate::resources::XrViews>,
+    views: Res<crate::resources::XrViews>,
     mut query: Query<(&mut Transform, &XrCameraType, &mut XRProjection)>,
 ) {
     let mut f = || -> Option<()> {
@@ -250,8 +250,7 @@ pub fn xr_camera_head_sync(
                 XrCameraType::Xr(eye) => *eye as usize,
                 XrCameraType::Flatscreen => return None,
             };
-            let v = views.lock().unwrap();
-            let view = v.get(view_idx)?;
+            let view = views.get(view_idx)?;
             xr_projection.fov = view.fov;
             transform.rotation = view.pose.orientation.to_quat();
             transform.translation = view.pose.position.to_vec3();
@@ -260,3 +259,19 @@ pub fn xr_camera_head_sync(
     };
     let _ = f();
 }
+
+/// only renders the XR cameras for frames the runtime is going to show
+pub fn xr_camera_should_render(
+    frame_state: Res<crate::resources::XrFrameState>,
+    frame_pending: Res<crate::resources::XrFramePending>,
+    mut query: Query<(&mut Camera, &XrCameraType)>,
+) {
+    let should_render = frame_pending.0 && frame_state.should_render;
+    for (mut camera, camera_type) in query.iter_mut() {
+        if let XrCameraType::Xr(_) = camera_type {
+            if camera.is_active != should_render {
+                camera.is_active = should_render;
+            }
+        }
+    }
+}